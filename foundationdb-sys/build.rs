@@ -12,9 +12,13 @@ const API_VERSION: i32 = 520;
 #[cfg(feature = "fdb-6_0")]
 const API_VERSION: i32 = 600;
 
-#[cfg(feature = "fdb-6_1")]
+// fdb-6_1 is enabled by default, so let fdb-6_2 take precedence over it
+#[cfg(all(feature = "fdb-6_1", not(feature = "fdb-6_2")))]
 const API_VERSION: i32 = 610;
 
+#[cfg(feature = "fdb-6_2")]
+const API_VERSION: i32 = 620;
+
 fn main() {
     let mut clang_args = Vec::new();
 
@@ -0,0 +1,211 @@
+// #[derive(Pack, Unpack)] for foundationdb_util::tuple
+//
+// Structs pack like Rust tuples of their fields: flat at the root of a key and as a nested
+// tuple within another tuple. Enum variants are identified by a string tag, the variant's
+// name unless renamed. Variants without fields pack as just the tag; variants with fields
+// pack as the tag followed by the fields, again flat at the root and nested otherwise.
+//
+// Attributes:
+//   #[tuple(skip)]            on a field: not packed, and unpacked as Default::default()
+//   #[tuple(flatten)]         on a field: packed flat, splicing its elements into this tuple
+//   #[tuple(rename = "tag")]  on a variant: use "tag" as the variant's tag
+
+extern crate proc_macro;
+
+mod pack;
+mod unpack;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Attribute, DeriveInput, Error, Fields, Generics, Ident, Lit,
+    LitStr, Member, Meta, NestedMeta, Type,
+};
+
+#[proc_macro_derive(Pack, attributes(tuple))]
+pub fn derive_pack(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    pack::derive(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+#[proc_macro_derive(Unpack, attributes(tuple))]
+pub fn derive_unpack(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    unpack::derive(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/* Attributes */
+
+#[derive(Default)]
+struct Attrs {
+    skip: bool,
+    flatten: bool,
+    rename: Option<LitStr>,
+}
+
+fn parse_attrs(attrs: &[Attribute]) -> syn::Result<Attrs> {
+    let mut out = Attrs::default();
+
+    for attr in attrs {
+        if !attr.path.is_ident("tuple") {
+            continue;
+        }
+
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(Error::new_spanned(meta, "expected #[tuple(...)]")),
+        };
+
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("skip") => {
+                    out.skip = true;
+                }
+                NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("flatten") => {
+                    out.flatten = true;
+                }
+                NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.path.is_ident("rename") => {
+                    match nv.lit {
+                        Lit::Str(ref s) => out.rename = Some(s.clone()),
+                        ref lit => return Err(Error::new_spanned(lit, "expected a string")),
+                    }
+                }
+                other => return Err(Error::new_spanned(other, "unknown tuple attribute")),
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/* Fields */
+
+struct Field<'a> {
+    member: Member,
+    ty: &'a Type,
+    // Local variable the field is bound to in generated code
+    binding: Ident,
+    skip: bool,
+    flatten: bool,
+}
+
+fn parse_fields(fields: &Fields) -> syn::Result<Vec<Field>> {
+    let mut out = Vec::new();
+
+    for (i, field) in fields.iter().enumerate() {
+        let attrs = parse_attrs(&field.attrs)?;
+        if let Some(rename) = attrs.rename {
+            return Err(Error::new_spanned(
+                rename,
+                "fields cannot be renamed, only variants",
+            ));
+        }
+        if attrs.skip && attrs.flatten {
+            return Err(Error::new_spanned(
+                field,
+                "a field cannot be both skipped and flattened",
+            ));
+        }
+
+        let member = match field.ident {
+            Some(ref ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(i.into()),
+        };
+
+        out.push(Field {
+            member,
+            ty: &field.ty,
+            binding: format_ident!("__f{}", i),
+            skip: attrs.skip,
+            flatten: attrs.flatten,
+        });
+    }
+
+    Ok(out)
+}
+
+// Binds the fields that get packed when matching a value, f.e. { a: __f0, b: _ }
+fn pattern(fields: &Fields, parsed: &[Field]) -> TokenStream2 {
+    let bindings = parsed.iter().map(|field| {
+        if field.skip {
+            quote!(_)
+        } else {
+            let binding = &field.binding;
+            quote!(#binding)
+        }
+    });
+    match fields {
+        Fields::Named(_) => {
+            let members = parsed.iter().map(|field| &field.member);
+            quote!({ #(#members: #bindings),* })
+        }
+        Fields::Unnamed(_) => quote!(( #(#bindings),* )),
+        Fields::Unit => quote!(),
+    }
+}
+
+// Builds a value from the fields' bindings, f.e. { a: __f0, b: __f1 }
+fn construct(fields: &Fields, parsed: &[Field]) -> TokenStream2 {
+    let bindings = parsed.iter().map(|field| &field.binding);
+    match fields {
+        Fields::Named(_) => {
+            let members = parsed.iter().map(|field| &field.member);
+            quote!({ #(#members: #bindings),* })
+        }
+        Fields::Unnamed(_) => quote!(( #(#bindings),* )),
+        Fields::Unit => quote!(),
+    }
+}
+
+// Whether anything gets packed for these fields
+fn has_packed(fields: &[Field]) -> bool {
+    fields.iter().any(|field| !field.skip)
+}
+
+/* Variants */
+
+// The tag of each variant, in declaration order
+fn variant_tags(data: &syn::DataEnum) -> syn::Result<Vec<LitStr>> {
+    let mut tags: Vec<LitStr> = Vec::new();
+
+    for variant in &data.variants {
+        let attrs = parse_attrs(&variant.attrs)?;
+        if attrs.skip || attrs.flatten {
+            return Err(Error::new_spanned(
+                variant,
+                "variants can only be renamed, not skipped or flattened",
+            ));
+        }
+
+        let tag = attrs
+            .rename
+            .unwrap_or_else(|| LitStr::new(&variant.ident.to_string(), variant.ident.span()));
+        if tags.iter().any(|t| t.value() == tag.value()) {
+            return Err(Error::new_spanned(tag, "duplicate variant tag"));
+        }
+        tags.push(tag);
+    }
+
+    Ok(tags)
+}
+
+/* Generics */
+
+// Requires every type parameter to implement the given trait
+fn add_bounds(generics: &Generics, bound: TokenStream2) -> Generics {
+    let mut generics = generics.clone();
+    let params: Vec<Ident> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    let where_clause = generics.make_where_clause();
+    for param in params {
+        where_clause.predicates.push(parse_quote!(#param: #bound));
+    }
+    generics
+}
@@ -0,0 +1,107 @@
+use crate::{add_bounds, has_packed, parse_fields, pattern, variant_tags, Field};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error};
+
+pub fn derive(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let generics = add_bounds(&input.generics, quote!(::foundationdb_util::tuple::Pack));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let (body, flat_body) = match input.data {
+        Data::Struct(ref data) => {
+            let fields = parse_fields(&data.fields)?;
+            let pattern = pattern(&data.fields, &fields);
+            let pack_fields = pack_fields(&fields);
+            let body = quote! {
+                ::foundationdb_util::tuple::derive::begin(out, nested);
+                ::foundationdb_util::tuple::Pack::pack_flat(self, out, nested);
+                ::foundationdb_util::tuple::derive::end(out, nested);
+            };
+            let flat_body = quote! {
+                let #name #pattern = self;
+                #pack_fields
+            };
+            (body, flat_body)
+        }
+        Data::Enum(ref data) => {
+            if data.variants.is_empty() {
+                return Err(Error::new_spanned(
+                    input,
+                    "cannot derive Pack for an enum without variants",
+                ));
+            }
+
+            // Variants with fields are wrapped in a nested tuple, unless flattened
+            let tags = variant_tags(data)?;
+            let mut arms = Vec::new();
+            let mut flat_arms = Vec::new();
+            for (variant, tag) in data.variants.iter().zip(tags) {
+                let ident = &variant.ident;
+                let fields = parse_fields(&variant.fields)?;
+                let pattern = pattern(&variant.fields, &fields);
+                if has_packed(&fields) {
+                    let pack_fields = pack_fields(&fields);
+                    arms.push(quote! {
+                        #name::#ident { .. } => {
+                            ::foundationdb_util::tuple::derive::begin(out, nested);
+                            ::foundationdb_util::tuple::Pack::pack_flat(self, out, nested);
+                            ::foundationdb_util::tuple::derive::end(out, nested);
+                        }
+                    });
+                    flat_arms.push(quote! {
+                        #name::#ident #pattern => {
+                            ::foundationdb_util::tuple::Pack::pack_element(#tag, out, nested);
+                            #pack_fields
+                        }
+                    });
+                } else {
+                    let arm = quote! {
+                        #name::#ident #pattern => ::foundationdb_util::tuple::Pack::pack(#tag, out, true),
+                    };
+                    arms.push(arm.clone());
+                    flat_arms.push(arm);
+                }
+            }
+
+            let body = quote! {
+                match self {
+                    #(#arms)*
+                }
+            };
+            let flat_body = quote! {
+                match self {
+                    #(#flat_arms)*
+                }
+            };
+            (body, flat_body)
+        }
+        Data::Union(_) => return Err(Error::new_spanned(input, "cannot derive Pack for a union")),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::foundationdb_util::tuple::Pack for #name #ty_generics #where_clause {
+            fn pack(&self, out: &mut ::std::vec::Vec<u8>, nested: bool) {
+                #body
+            }
+
+            fn pack_flat(&self, out: &mut ::std::vec::Vec<u8>, nested: bool) {
+                #flat_body
+            }
+        }
+
+        impl #impl_generics ::foundationdb_util::tuple::Tuple for #name #ty_generics #where_clause {}
+    })
+}
+
+fn pack_fields(fields: &[Field]) -> TokenStream {
+    let packs = fields.iter().filter(|field| !field.skip).map(|field| {
+        let binding = &field.binding;
+        if field.flatten {
+            quote!(::foundationdb_util::tuple::Pack::pack_flat(#binding, out, nested);)
+        } else {
+            quote!(::foundationdb_util::tuple::Pack::pack_element(#binding, out, nested);)
+        }
+    });
+    quote!(#(#packs)*)
+}
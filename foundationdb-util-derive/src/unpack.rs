@@ -0,0 +1,135 @@
+use crate::{add_bounds, construct, has_packed, parse_fields, variant_tags, Field};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, Data, DeriveInput, Error, GenericParam, LifetimeDef};
+
+pub fn derive(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let generics = add_bounds(
+        &input.generics,
+        quote!(::foundationdb_util::tuple::Unpack<'de>),
+    );
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+
+    // Borrowed fields may borrow from the input, so it must outlive all of the type's lifetimes
+    let mut de: LifetimeDef = parse_quote!('de);
+    de.bounds
+        .extend(generics.lifetimes().map(|def| def.lifetime.clone()));
+    let mut impl_generics = generics.clone();
+    impl_generics.params.insert(0, GenericParam::Lifetime(de));
+    let (impl_generics, _, _) = impl_generics.split_for_impl();
+
+    let (body, flat_body) = match input.data {
+        Data::Struct(ref data) => {
+            let fields = parse_fields(&data.fields)?;
+            let construct = construct(&data.fields, &fields);
+            let unpack_fields = unpack_fields(&fields);
+            let body = quote! {
+                let inp = ::foundationdb_util::tuple::derive::expect_begin(inp, nested)?;
+                let (val, inp) = <Self as ::foundationdb_util::tuple::Unpack<'de>>::unpack_flat(inp, nested)?;
+                let inp = ::foundationdb_util::tuple::derive::expect_end(inp, nested)?;
+                ::std::result::Result::Ok((val, inp))
+            };
+            let flat_body = quote! {
+                #unpack_fields
+                ::std::result::Result::Ok((#name #construct, inp))
+            };
+            (body, flat_body)
+        }
+        Data::Enum(ref data) => {
+            if data.variants.is_empty() {
+                return Err(Error::new_spanned(
+                    input,
+                    "cannot derive Unpack for an enum without variants",
+                ));
+            }
+
+            let tags = variant_tags(data)?;
+            let mut arms = Vec::new();
+            let mut flat_arms = Vec::new();
+            for (variant, tag) in data.variants.iter().zip(tags) {
+                let ident = &variant.ident;
+                let fields = parse_fields(&variant.fields)?;
+                let construct = construct(&variant.fields, &fields);
+                let unpack_fields = unpack_fields(&fields);
+                // Variants with fields are wrapped in a nested tuple unless at the root
+                let guard = if has_packed(&fields) {
+                    quote!(wrapped || !nested)
+                } else {
+                    quote!(!wrapped)
+                };
+                arms.push(quote! {
+                    #tag if #guard => {
+                        #unpack_fields
+                        (#name::#ident #construct, inp)
+                    }
+                });
+                // Flattened, nothing is wrapped
+                flat_arms.push(quote! {
+                    #tag => {
+                        #unpack_fields
+                        (#name::#ident #construct, inp)
+                    }
+                });
+            }
+
+            let body = quote! {
+                let (tag, wrapped, inp) = ::foundationdb_util::tuple::derive::unpack_tag(inp, nested)?;
+                let (val, inp) = match &*tag {
+                    #(#arms)*
+                    _ => return ::std::result::Result::Err(::foundationdb_util::tuple::UnpackError::BadEncoding),
+                };
+                let inp = ::foundationdb_util::tuple::derive::expect_end(inp, wrapped)?;
+                ::std::result::Result::Ok((val, inp))
+            };
+            let flat_body = quote! {
+                let (tag, _, inp) = ::foundationdb_util::tuple::derive::unpack_tag(inp, false)?;
+                let (val, inp) = match &*tag {
+                    #(#flat_arms)*
+                    _ => return ::std::result::Result::Err(::foundationdb_util::tuple::UnpackError::BadEncoding),
+                };
+                ::std::result::Result::Ok((val, inp))
+            };
+            (body, flat_body)
+        }
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                input,
+                "cannot derive Unpack for a union",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::foundationdb_util::tuple::Unpack<'de> for #name #ty_generics #where_clause {
+            fn unpack(
+                inp: &'de [u8],
+                nested: bool,
+            ) -> ::std::result::Result<(Self, &'de [u8]), ::foundationdb_util::tuple::UnpackError> {
+                #body
+            }
+
+            fn unpack_flat(
+                inp: &'de [u8],
+                nested: bool,
+            ) -> ::std::result::Result<(Self, &'de [u8]), ::foundationdb_util::tuple::UnpackError> {
+                #flat_body
+            }
+        }
+    })
+}
+
+fn unpack_fields(fields: &[Field]) -> TokenStream {
+    let unpacks = fields.iter().map(|field| {
+        let binding = &field.binding;
+        let ty = field.ty;
+        if field.skip {
+            quote!(let #binding: #ty = ::std::default::Default::default();)
+        } else if field.flatten {
+            quote!(let (#binding, inp) = <#ty as ::foundationdb_util::tuple::Unpack<'de>>::unpack_flat(inp, nested)?;)
+        } else {
+            quote!(let (#binding, inp) = <#ty as ::foundationdb_util::tuple::Unpack<'de>>::unpack(inp, true)?;)
+        }
+    });
+    quote!(#(#unpacks)*)
+}
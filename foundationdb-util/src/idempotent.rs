@@ -0,0 +1,258 @@
+use crate::subspace::Subspace;
+use crate::transact;
+use byteorder::{BigEndian, ByteOrder};
+use foundationdb::{
+    Database, Error, ErrorPredicate, FailedTransaction, GetRangeOpt, KeySelector, StreamingMode,
+    Transaction,
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+fn since_epoch() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_else(|_| Duration::from_secs(0))
+}
+
+// A random (v4) UUID, so markers of different attempts, processes and hosts don't collide
+fn new_marker(markers: &Subspace) -> Vec<u8> {
+    markers.pack(&(Uuid::new_v4(),))
+}
+
+// Markers hold the time they were written, in seconds since the epoch
+fn marker_value() -> [u8; 8] {
+    let mut value = [0; 8];
+    BigEndian::write_u64(&mut value, since_epoch().as_secs());
+    value
+}
+
+// Markers are only needed until the transaction that wrote them is known to have committed.
+// Failing to clear them here is harmless, as clear_stale_markers will get them eventually.
+fn clear_markers(db: &Database, markers: &[Vec<u8>]) {
+    let _: Result<(), Error> = transact(db, |tran| {
+        for marker in markers {
+            tran.clear(marker);
+        }
+        Ok(())
+    });
+}
+
+// Like transact, but for closures that must not be applied twice, such as ones that increment a
+// counter. Every attempt also writes a marker key under the given subspace. When a commit fails
+// with an error that means it may have succeeded anyway (such as commit_unknown_result), the next
+// attempt first looks for that attempt's marker, and if it's there, returns the value the closure
+// produced back then instead of running the closure again.
+pub fn transact_idempotent<Fun, OutVal, OutErr>(
+    db: &Database,
+    markers: &Subspace,
+    f: Fun,
+) -> Result<OutVal, OutErr>
+where
+    Fun: FnMut(&Transaction) -> Result<OutVal, OutErr>,
+    OutErr: From<Error>,
+{
+    transact_idempotent_with(db, markers, f, |tran| tran.commit().map(|_| ()))
+}
+
+// Commits through the given function, so tests can make commits fail the way the cluster might
+fn transact_idempotent_with<Fun, Commit, OutVal, OutErr>(
+    db: &Database,
+    markers: &Subspace,
+    mut f: Fun,
+    mut commit: Commit,
+) -> Result<OutVal, OutErr>
+where
+    Fun: FnMut(&Transaction) -> Result<OutVal, OutErr>,
+    Commit: FnMut(Transaction) -> Result<(), FailedTransaction>,
+    OutErr: From<Error>,
+{
+    // Markers of attempts that may have committed, with what the closure returned in each
+    let mut maybe_committed: Vec<(Vec<u8>, OutVal)> = Vec::new();
+    let mut tran = db.create_transaction()?;
+    loop {
+        let mut committed = None;
+        for (i, (marker, _)) in maybe_committed.iter().enumerate() {
+            if tran.get(marker, false)?.is_some() {
+                committed = Some(i);
+                break;
+            }
+        }
+
+        if let Some(i) = committed {
+            let (marker, val) = maybe_committed.swap_remove(i);
+            let mut written = vec![marker];
+            written.extend(maybe_committed.into_iter().map(|(marker, _)| marker));
+            clear_markers(db, &written);
+            return Ok(val);
+        }
+
+        let marker = new_marker(markers);
+        let val = f(&tran)?;
+        tran.set(&marker, &marker_value());
+
+        match commit(tran) {
+            Ok(()) => {
+                let mut written = vec![marker];
+                written.extend(maybe_committed.into_iter().map(|(marker, _)| marker));
+                clear_markers(db, &written);
+                return Ok(val);
+            }
+            Err(fail_tran) => {
+                let maybe = fail_tran.error().is(ErrorPredicate::MaybeCommitted);
+                match fail_tran.on_error() {
+                    Ok(reset_tran) => tran = reset_tran,
+                    Err(fail_tran) => return Err(OutErr::from(fail_tran.into_error())),
+                }
+                if maybe {
+                    maybe_committed.push((marker, val));
+                }
+            }
+        }
+    }
+}
+
+// Clears markers left behind by transact_idempotent calls that didn't get to clean up after
+// themselves, such as those of crashed processes. Only markers older than max_age are cleared, so
+// max_age should comfortably exceed how long any transact_idempotent call may run. Returns the
+// number of markers cleared.
+pub fn clear_stale_markers(
+    db: &Database,
+    markers: &Subspace,
+    max_age: Duration,
+) -> Result<usize, Error> {
+    let cutoff = since_epoch().as_secs().saturating_sub(max_age.as_secs());
+    let (begin, end) = markers.range(&());
+    let mut next = begin;
+    let mut cleared = 0;
+
+    loop {
+        let (count, last, more) = transact(db, |tran| {
+            let opt = GetRangeOpt {
+                begin_selector: KeySelector::first_greater_or_equal(&next),
+                end_selector: KeySelector::first_greater_or_equal(&end),
+                limit: 1000,
+                mode: StreamingMode::WantAll,
+                ..GetRangeOpt::default()
+            };
+            let kvs = tran.get_range(&opt)?;
+
+            let mut count = 0;
+            for kv in kvs.iter() {
+                let value = kv.value();
+                if value.len() == 8 && BigEndian::read_u64(value) < cutoff {
+                    tran.clear(kv.key());
+                    count += 1;
+                }
+            }
+
+            let last = kvs.iter().last().map(|kv| kv.key().to_vec());
+            Ok::<_, Error>((count, last, kvs.more()))
+        })?;
+
+        cleared += count;
+
+        match last {
+            Some(last) if more => {
+                next = last;
+                next.push(0x00);
+            }
+            _ => return Ok(cleared),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{marker_value, since_epoch, transact_idempotent_with};
+    use crate::test::database;
+    use crate::{clear_stale_markers, transact_idempotent, Subspace};
+    use byteorder::{BigEndian, ByteOrder, LittleEndian};
+    use foundationdb::{Database, Error, GetRangeOpt, KeySelector, MutationType};
+    use std::time::Duration;
+
+    fn read_counter(db: &Database, key: &[u8]) -> i64 {
+        db.get(key)
+            .unwrap()
+            .map_or(0, |value| LittleEndian::read_i64(value.as_ref()))
+    }
+
+    fn marker_keys(db: &Database, markers: &Subspace) -> Vec<Vec<u8>> {
+        let (begin, end) = markers.range(&());
+        let opt = GetRangeOpt {
+            begin_selector: KeySelector::first_greater_or_equal(&begin),
+            end_selector: KeySelector::first_greater_or_equal(&end),
+            ..GetRangeOpt::default()
+        };
+        let kvs = db.get_range(&opt).unwrap();
+        kvs.iter().map(|kv| kv.key().to_vec()).collect()
+    }
+
+    #[test]
+    fn test_transact_idempotent() {
+        let database = database();
+        let markers = Subspace::new(&("markers",));
+
+        let val: Result<i32, Error> = transact_idempotent(&database, &markers, |tran| {
+            tran.atomic_op(b"counter", &1i64.to_le_bytes(), MutationType::Add);
+            Ok(42)
+        });
+        assert_eq!(val, Ok(42));
+
+        clear_stale_markers(&database, &markers, Duration::from_secs(0)).unwrap();
+    }
+
+    #[test]
+    fn test_transact_idempotent_maybe_committed() {
+        let database = database();
+        let markers = Subspace::new(&("maybe_committed_markers",));
+        let counter = b"maybe_committed_counter";
+        database.clear(counter).unwrap();
+
+        // The first commit goes through, but reports commit_unknown_result like a commit whose
+        // reply got lost
+        let mut commits = 0;
+        let mut runs = 0;
+        let val: Result<i32, Error> = transact_idempotent_with(
+            &database,
+            &markers,
+            |tran| {
+                runs += 1;
+                tran.atomic_op(counter, &1i64.to_le_bytes(), MutationType::Add);
+                Ok(runs)
+            },
+            |tran| {
+                commits += 1;
+                let res = tran.commit().map(|_| ());
+                if commits == 1 && res.is_ok() {
+                    let tran = database.create_transaction().unwrap();
+                    return Err(tran.into_failed(Error::from_code(1021)));
+                }
+                res
+            },
+        );
+
+        assert_eq!(val, Ok(1));
+        assert_eq!(runs, 1);
+        assert_eq!(read_counter(&database, counter), 1);
+        assert!(marker_keys(&database, &markers).is_empty());
+    }
+
+    #[test]
+    fn test_clear_stale_markers() {
+        let database = database();
+        let markers = Subspace::new(&("stale_markers",));
+        let (begin, end) = markers.range(&());
+        database.clear_range(&begin, &end).unwrap();
+
+        let stale = markers.pack(&("stale",));
+        let mut value = [0; 8];
+        BigEndian::write_u64(&mut value, since_epoch().as_secs() - 3600);
+        database.set(&stale, &value).unwrap();
+        let fresh = markers.pack(&("fresh",));
+        database.set(&fresh, &marker_value()).unwrap();
+
+        let cleared = clear_stale_markers(&database, &markers, Duration::from_secs(60)).unwrap();
+        assert_eq!(cleared, 1);
+        assert_eq!(marker_keys(&database, &markers), vec![fresh]);
+    }
+}
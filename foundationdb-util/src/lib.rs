@@ -1,25 +1,132 @@
+// Lets code generated by #[derive(Pack, Unpack)] name this crate in its own tests
+#[cfg(test)]
+extern crate self as foundationdb_util;
+
+// Needs uuid for its commit markers
+#[cfg(feature = "uuid")]
+mod idempotent;
+mod retry;
 mod subspace;
-mod tuple;
+pub mod tuple;
+mod write_buffer;
 
+#[cfg(feature = "uuid")]
+pub use idempotent::{clear_stale_markers, transact_idempotent};
+pub use retry::{RetryError, RetryPolicy};
 pub use subspace::Subspace;
+pub use write_buffer::{Savepoint, WriteBuffer};
 
-use foundationdb::{Database, Error, Transaction};
+use foundationdb::{Database, Error, Mutation, Transaction};
+use std::time::Instant;
 
-pub fn transact<Fun, OutVal, OutErr>(db: &Database, mut f: Fun) -> Result<OutVal, OutErr>
+pub fn transact<Fun, OutVal, OutErr>(db: &Database, f: Fun) -> Result<OutVal, OutErr>
 where
     Fun: FnMut(&Transaction) -> Result<OutVal, OutErr>,
     OutErr: From<Error>,
 {
-    let mut tran = db.create_transaction()?;
+    transact_with(db, &mut RetryPolicy::default(), f).map_err(RetryError::into_error)
+}
+
+// Like transact, but also returns the mutations made by the attempt that committed
+pub fn transact_recorded<Fun, OutVal, OutErr>(
+    db: &Database,
+    mut f: Fun,
+) -> Result<(OutVal, Vec<Mutation>), OutErr>
+where
+    Fun: FnMut(&Transaction) -> Result<OutVal, OutErr>,
+    OutErr: From<Error>,
+{
+    transact(db, |tran| {
+        tran.record_mutations();
+        let val = f(tran)?;
+        Ok((val, tran.mutations()))
+    })
+}
+
+// Runs f once and returns the mutations it made, cancelling the transaction instead of
+// committing it
+pub fn dry_run<Fun, OutVal, OutErr>(
+    db: &Database,
+    f: Fun,
+) -> Result<(OutVal, Vec<Mutation>), OutErr>
+where
+    Fun: FnOnce(&Transaction) -> Result<OutVal, OutErr>,
+    OutErr: From<Error>,
+{
+    let tran = db.create_transaction()?;
+    tran.record_mutations();
+    let val = f(&tran)?;
+    let mutations = tran.mutations();
+    tran.cancel();
+    Ok((val, mutations))
+}
+
+pub fn transact_with<Fun, OutVal, OutErr>(
+    db: &Database,
+    policy: &mut RetryPolicy,
+    mut f: Fun,
+) -> Result<OutVal, RetryError<OutErr>>
+where
+    Fun: FnMut(&Transaction) -> Result<OutVal, OutErr>,
+    OutErr: From<Error>,
+{
+    let start = Instant::now();
+    let mut attempts = 0;
+    let mut history = Vec::new();
+
+    let mut tran = match db.create_transaction() {
+        Ok(tran) => tran,
+        Err(err) => {
+            return Err(RetryError {
+                attempts,
+                history,
+                error: OutErr::from(err),
+            })
+        }
+    };
+
     loop {
-        let val = f(&tran)?;
-        match tran.commit() {
+        attempts += 1;
+
+        let val = match f(&tran) {
+            Ok(val) => val,
+            Err(error) => {
+                return Err(RetryError {
+                    attempts,
+                    history,
+                    error,
+                })
+            }
+        };
+
+        let fail_tran = match tran.commit() {
             Ok(_) => return Ok(val),
-            Err(fail_tran) => match fail_tran.on_error() {
-                Ok(reset_tran) => tran = reset_tran,
-                Err(fail_tran) => return Err(OutErr::from(fail_tran.into_error())),
-            },
+            Err(fail_tran) => fail_tran,
+        };
+
+        let err = fail_tran.error();
+        history.push(err.code());
+
+        if !policy.should_retry(attempts, start) {
+            return Err(RetryError {
+                attempts,
+                history,
+                error: OutErr::from(err),
+            });
+        }
+
+        match fail_tran.on_error() {
+            Ok(reset_tran) => tran = reset_tran,
+            Err(fail_tran) => {
+                return Err(RetryError {
+                    attempts,
+                    history,
+                    error: OutErr::from(fail_tran.into_error()),
+                })
+            }
         }
+
+        policy.before_retry(attempts, &err);
     }
 }
 
@@ -27,13 +134,34 @@ where
 
 #[cfg(test)]
 mod test {
-    use crate::transact;
-    use foundationdb::{Database, Error, Network};
+    use crate::{dry_run, transact, transact_with, RetryPolicy};
+    use foundationdb::{Database, Error, Mutation, Network, Transaction};
+    use std::cell::Cell;
+    use std::mem;
+    use std::rc::Rc;
+    use std::sync::Once;
+    use std::time::Duration;
+
+    // FDB lets a process set up its network only once, and can't restart it once stopped, so
+    // every test shares one that runs until the process exits
+    pub(crate) fn database() -> Database {
+        static NETWORK: Once = Once::new();
+        NETWORK.call_once(|| mem::forget(Network::new().unwrap()));
+        Database::new().unwrap()
+    }
+
+    // Reads key and has another transaction write it before writing it too, so that committing
+    // tran fails with not_committed
+    fn conflict(db: &Database, tran: &Transaction, key: &[u8]) -> Result<(), Error> {
+        tran.get(key, false)?;
+        db.set(key, b"other")?;
+        tran.set(key, b"tran");
+        Ok(())
+    }
 
     #[test]
     fn test_general() {
-        let _network = Network::new().unwrap();
-        let database = Database::new().unwrap();
+        let database = database();
 
         let _: Result<(), Error> = transact(&database, |tran| {
             tran.set(b"hello", b"world");
@@ -43,4 +171,71 @@ mod test {
             Ok(())
         });
     }
+
+    #[test]
+    fn test_transact_with() {
+        let database = database();
+        let not_committed = 1020;
+
+        // Retries the attempt that conflicted
+        let retried = Rc::new(Cell::new(Vec::new()));
+        let on_retry = retried.clone();
+        let mut policy = RetryPolicy::new()
+            .max_attempts(3)
+            .deadline(Duration::from_secs(5))
+            .on_retry(move |attempts, err| {
+                let mut retried = on_retry.take();
+                retried.push((attempts, err.code()));
+                on_retry.set(retried);
+            });
+        let mut attempts = 0;
+        let res = transact_with(&database, &mut policy, |tran| {
+            attempts += 1;
+            if attempts == 1 {
+                conflict(&database, tran, b"transact_with")?;
+            }
+            Ok::<_, Error>(attempts)
+        });
+        assert_eq!(res.unwrap(), 2);
+        assert_eq!(retried.take(), vec![(1, not_committed)]);
+
+        // Gives up after max_attempts, with the errors of every attempt
+        let mut policy = RetryPolicy::new().max_attempts(2);
+        let mut attempts = 0;
+        let res: Result<(), _> = transact_with(&database, &mut policy, |tran| {
+            attempts += 1;
+            conflict(&database, tran, b"transact_with")
+        });
+        let err = res.unwrap_err();
+        assert_eq!(attempts, 2);
+        assert_eq!(err.attempts, 2);
+        assert_eq!(err.history, vec![not_committed, not_committed]);
+        assert_eq!(err.error.code(), not_committed);
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let database = database();
+
+        let res: Result<_, Error> = dry_run(&database, |tran| {
+            tran.set(b"hello", b"world");
+            tran.clear(b"hello");
+            Ok(())
+        });
+
+        let ((), mutations) = res.unwrap();
+        assert_eq!(
+            mutations,
+            vec![
+                Mutation::Set {
+                    key: b"hello".to_vec(),
+                    value: b"world".to_vec(),
+                },
+                Mutation::Clear {
+                    key: b"hello".to_vec(),
+                },
+            ]
+        );
+        assert!(database.get(b"hello").unwrap().is_none());
+    }
 }
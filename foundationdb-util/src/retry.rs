@@ -0,0 +1,108 @@
+use foundationdb::Error;
+use std::error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::time::{Duration, Instant};
+
+type Backoff = Box<dyn Fn(u32) -> Duration>;
+type OnRetry = Box<dyn FnMut(u32, &Error)>;
+
+// Controls how transact_with retries. The default policy retries for as long as on_error allows,
+// like transact.
+#[derive(Default)]
+pub struct RetryPolicy {
+    max_attempts: Option<u32>,
+    deadline: Option<Duration>,
+    backoff: Option<Backoff>,
+    on_retry: Option<OnRetry>,
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Gives up once this many attempts have failed
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    // Gives up instead of retrying once this much time has passed since the first attempt began
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    // Sleeps for the returned duration before the next attempt, on top of the delay on_error
+    // already applies. Receives the number of attempts made so far.
+    pub fn backoff<F>(mut self, backoff: F) -> Self
+    where
+        F: Fn(u32) -> Duration + 'static,
+    {
+        self.backoff = Some(Box::new(backoff));
+        self
+    }
+
+    // Called before each retry with the number of attempts made so far and the error that
+    // failed the last one
+    pub fn on_retry<F>(mut self, on_retry: F) -> Self
+    where
+        F: FnMut(u32, &Error) + 'static,
+    {
+        self.on_retry = Some(Box::new(on_retry));
+        self
+    }
+
+    pub(crate) fn should_retry(&self, attempts: u32, start: Instant) -> bool {
+        if let Some(max_attempts) = self.max_attempts {
+            if attempts >= max_attempts {
+                return false;
+            }
+        }
+
+        if let Some(deadline) = self.deadline {
+            let delay = self.backoff.as_ref().map_or(Duration::from_secs(0), |b| b(attempts));
+            if start.elapsed() + delay >= deadline {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub(crate) fn before_retry(&mut self, attempts: u32, err: &Error) {
+        if let Some(on_retry) = self.on_retry.as_mut() {
+            on_retry(attempts, err);
+        }
+
+        if let Some(backoff) = self.backoff.as_ref() {
+            std::thread::sleep(backoff(attempts));
+        }
+    }
+}
+
+// The error returned by transact_with, along with the number of attempts made and the codes of
+// the FoundationDB errors that failed each of them
+#[derive(Debug)]
+pub struct RetryError<E> {
+    pub attempts: u32,
+    pub history: Vec<i32>,
+    pub error: E,
+}
+
+impl<E> RetryError<E> {
+    pub fn into_error(self) -> E {
+        self.error
+    }
+}
+
+impl<E> Display for RetryError<E>
+where
+    E: Display,
+{
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{} (after {} attempts)", self.error, self.attempts)
+    }
+}
+
+impl<E> error::Error for RetryError<E> where E: error::Error {}
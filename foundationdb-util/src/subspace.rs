@@ -1,4 +1,4 @@
-use crate::tuple::{Pack, Tuple, Unpack, UnpackError};
+use crate::tuple::{pack_into_with_versionstamp, Pack, PackError, Tuple, Unpack, UnpackError};
 
 pub struct Subspace {
     prefix_bytes: Vec<u8>,
@@ -17,7 +17,14 @@ impl Subspace {
         bytes
     }
 
-    pub fn unpack<T: Tuple + Unpack>(&self, inp: &[u8]) -> Result<T, UnpackError> {
+    // Packs a tuple holding one incomplete versionstamp, for use with SetVersionstampedKey
+    pub fn pack_with_versionstamp<T: Tuple + Pack>(&self, tuple: &T) -> Result<Vec<u8>, PackError> {
+        let mut bytes = self.prefix_bytes.clone();
+        pack_into_with_versionstamp(tuple, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub fn unpack<'de, T: Tuple + Unpack<'de>>(&self, inp: &'de [u8]) -> Result<T, UnpackError> {
         if inp.len() < self.prefix_bytes.len() {
             return Err(UnpackError::MissingPrefix);
         }
@@ -54,6 +61,10 @@ impl Subspace {
             && &inp[..self.prefix_bytes.len()] == self.prefix_bytes.as_slice()
     }
 
+    pub fn key(&self) -> &[u8] {
+        self.prefix_bytes.as_slice()
+    }
+
     pub fn subspace<T: Tuple + Pack>(&self, tuple: &T) -> Self {
         let mut prefix_bytes = self.prefix_bytes.clone();
         tuple.pack(&mut prefix_bytes, false);
@@ -70,7 +81,7 @@ impl AsRef<[u8]> for Subspace {
 #[cfg(test)]
 mod test {
     use super::Subspace;
-    use crate::tuple::UnpackError;
+    use crate::tuple::{UnpackError, Versionstamp};
 
     #[test]
     fn subspace() {
@@ -90,10 +101,25 @@ mod test {
         let (field,): (String,) = s2.unpack(&k1).unwrap();
         assert_eq!(field, "state");
 
+        // Borrowed from k1
+        let (eid, field): (i32, &str) = s1.unpack(&k1).unwrap();
+        assert_eq!((eid, field), (356, "state"));
+
         let r: Result<(String,), _> = s3.unpack(&k1);
         assert_eq!(r, Err(UnpackError::MissingPrefix));
 
         let s4 = s1.subspace(&(356,));
         assert_eq!(s2.key(), s4.key());
     }
+
+    #[test]
+    fn subspace_pack_with_versionstamp() {
+        let s = Subspace::new(&("log",));
+        let key = s.pack_with_versionstamp(&(Versionstamp::incomplete(0),)).unwrap();
+        let (packed, offset) = key.split_at(key.len() - 4);
+        assert_eq!(offset, &[s.key().len() as u8 + 1, 0x00, 0x00, 0x00]);
+
+        let (vs,): (Versionstamp,) = s.unpack(packed).unwrap();
+        assert_eq!(vs, Versionstamp::incomplete(0));
+    }
 }
@@ -1,25 +1,92 @@
+#[cfg(feature = "num-bigint")]
+mod bigint;
 mod bool;
 mod bytes;
+#[cfg(test)]
+mod conformance;
+#[cfg(feature = "serde")]
+mod de;
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod derive;
+mod element;
 mod float;
 mod integer;
 mod option;
+mod printable;
+#[cfg(feature = "serde")]
+mod ser;
 mod tuple;
 #[cfg(feature = "uuid")]
 mod uuid;
 mod versionstamp;
 
+use std::borrow::Cow;
 use std::convert::Infallible;
 use std::num::TryFromIntError;
+use std::str::Utf8Error;
 use std::string::FromUtf8Error;
 
+#[cfg(feature = "serde")]
+pub use crate::tuple::de::{from_slice, Deserializer};
+pub use crate::tuple::element::Element;
+pub use crate::tuple::printable::{parse_printable, ParseError, Printable};
+#[cfg(feature = "serde")]
+pub use crate::tuple::ser::{to_vec, SerdeError, Serializer};
 pub use crate::tuple::tuple::Tuple;
+use crate::tuple::tuple::{pack_items, unpack_items};
+pub use crate::tuple::versionstamp::{pack_with_versionstamp, PackError, Versionstamp};
+pub(crate) use crate::tuple::versionstamp::pack_into_with_versionstamp;
+#[cfg(feature = "derive")]
+pub use foundationdb_util_derive::{Pack, Unpack};
 
 pub trait Pack {
     fn pack(&self, out: &mut Vec<u8>, nested: bool);
+
+    // How tuples pack their elements. in_nested says whether the tuple is itself nested, where
+    // nulls have to be escaped; only Option and Element care.
+    #[doc(hidden)]
+    fn pack_element(&self, out: &mut Vec<u8>, in_nested: bool) {
+        let _ = in_nested;
+        self.pack(out, true)
+    }
+
+    // How #[tuple(flatten)] fields pack. Tuples splice their elements into the enclosing tuple,
+    // escaped when that one is nested; anything else packs as one of its elements.
+    #[doc(hidden)]
+    fn pack_flat(&self, out: &mut Vec<u8>, in_nested: bool) {
+        if in_nested {
+            self.pack_element(out, true)
+        } else {
+            self.pack(out, false)
+        }
+    }
+
+    // How [Self] and Vec<Self> pack: as a tuple of the items, except that u8 packs as bytes
+    #[doc(hidden)]
+    fn pack_slice(items: &[Self], out: &mut Vec<u8>, nested: bool)
+    where
+        Self: Sized,
+    {
+        pack_items(items, out, nested)
+    }
 }
 
-pub trait Unpack: Sized {
-    fn unpack(inp: &[u8], nested: bool) -> Result<(Self, &[u8]), UnpackError>;
+// Values may borrow from the input they're unpacked from, f.e. &'de str
+pub trait Unpack<'de>: Sized {
+    fn unpack(inp: &'de [u8], nested: bool) -> Result<(Self, &'de [u8]), UnpackError>;
+
+    // The counterpart of Pack::pack_flat
+    #[doc(hidden)]
+    fn unpack_flat(inp: &'de [u8], in_nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
+        Self::unpack(inp, in_nested)
+    }
+
+    // The counterpart of Pack::pack_slice
+    #[doc(hidden)]
+    fn unpack_vec(inp: &'de [u8], nested: bool) -> Result<(Vec<Self>, &'de [u8]), UnpackError> {
+        unpack_items(inp, nested)
+    }
 }
 
 // Allows you to pack a &&str, f.e., which happens in the case of tuples like (&str,)
@@ -30,6 +97,31 @@ where
     fn pack(&self, out: &mut Vec<u8>, nested: bool) {
         T::pack(*self, out, nested)
     }
+
+    fn pack_element(&self, out: &mut Vec<u8>, in_nested: bool) {
+        T::pack_element(*self, out, in_nested)
+    }
+
+    fn pack_flat(&self, out: &mut Vec<u8>, in_nested: bool) {
+        T::pack_flat(*self, out, in_nested)
+    }
+}
+
+impl<T> Pack for Cow<'_, T>
+where
+    T: Pack + ToOwned + ?Sized,
+{
+    fn pack(&self, out: &mut Vec<u8>, nested: bool) {
+        T::pack(self, out, nested)
+    }
+
+    fn pack_element(&self, out: &mut Vec<u8>, in_nested: bool) {
+        T::pack_element(self, out, in_nested)
+    }
+
+    fn pack_flat(&self, out: &mut Vec<u8>, in_nested: bool) {
+        T::pack_flat(self, out, in_nested)
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -38,6 +130,8 @@ pub enum UnpackError {
     OutOfData,
     OutOfRange,
     BadEncoding,
+    // The element can't be borrowed because it holds escaped nulls. Unpack a Cow instead.
+    EscapedNull,
     // For Subspace::unpack
     MissingPrefix,
     TrailingData,
@@ -49,6 +143,12 @@ impl From<FromUtf8Error> for UnpackError {
     }
 }
 
+impl From<Utf8Error> for UnpackError {
+    fn from(_: Utf8Error) -> Self {
+        UnpackError::BadEncoding
+    }
+}
+
 impl From<TryFromIntError> for UnpackError {
     fn from(_: TryFromIntError) -> Self {
         UnpackError::OutOfRange
@@ -90,7 +190,7 @@ mod test {
 
     pub fn test_pack_unpack<T>(in_val: T, buf: &mut Vec<u8>)
     where
-        T: Pack + Unpack + Debug + PartialEq,
+        T: Pack + for<'de> Unpack<'de> + Debug + PartialEq,
     {
         buf.clear();
         T::pack(&in_val, buf, false);
@@ -0,0 +1,99 @@
+use crate::tuple::integer::{pack_magnitude, unpack_raw};
+use crate::tuple::{Pack, Unpack, UnpackError};
+use num_bigint::{BigInt, BigUint, Sign};
+
+fn pack_bytes_be(negative: bool, mag: &[u8], out: &mut Vec<u8>) {
+    let start = mag.iter().position(|&b| b != 0).unwrap_or(mag.len());
+    pack_magnitude(negative, &mag[start..], out);
+}
+
+fn unpack_biguint(inp: &[u8]) -> Result<((bool, BigUint), &[u8]), UnpackError> {
+    let (negative, raw, inp) = unpack_raw(inp)?;
+    let out = if negative {
+        BigUint::from_bytes_be(&raw.iter().map(|b| !b).collect::<Vec<_>>())
+    } else {
+        BigUint::from_bytes_be(raw)
+    };
+    Ok(((negative, out), inp))
+}
+
+impl Pack for BigInt {
+    fn pack(&self, out: &mut Vec<u8>, _nested: bool) {
+        let (sign, mag) = self.to_bytes_be();
+        pack_bytes_be(sign == Sign::Minus, &mag, out);
+    }
+}
+
+impl<'de> Unpack<'de> for BigInt {
+    fn unpack(inp: &'de [u8], _nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
+        let ((negative, mag), inp) = unpack_biguint(inp)?;
+        let sign = if negative { Sign::Minus } else { Sign::Plus };
+        Ok((BigInt::from_biguint(sign, mag), inp))
+    }
+}
+
+impl Pack for BigUint {
+    fn pack(&self, out: &mut Vec<u8>, _nested: bool) {
+        pack_bytes_be(false, &self.to_bytes_be(), out);
+    }
+}
+
+impl<'de> Unpack<'de> for BigUint {
+    fn unpack(inp: &'de [u8], _nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
+        let ((negative, out), inp) = unpack_biguint(inp)?;
+        if negative && out != BigUint::from(0u8) {
+            return Err(UnpackError::OutOfRange);
+        }
+        Ok((out, inp))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tuple::test::{test_pack, test_pack_unpack};
+    use crate::tuple::{Pack, Unpack};
+    use num_bigint::{BigInt, BigUint};
+    use rand::random;
+
+    #[test]
+    fn test_pack_bigint() {
+        let mut buf = Vec::new();
+        let two_64 = BigInt::from(1u128 << 64);
+        test_pack(BigInt::from(0), &mut buf, &[0x14]);
+        test_pack(BigInt::from(-5), &mut buf, &[0x13, 0xfa]);
+        test_pack(two_64.clone(), &mut buf, &[0x1d, 0x09, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        test_pack(-two_64.clone(), &mut buf, &[0x0b, 0xf6, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        test_pack(BigUint::from(u64::max_value()), &mut buf, &[0x1d, 0x08, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+
+        // Beyond what i128 can hold
+        let big = BigInt::from(1) << 200;
+        let mut expected = vec![0x1d, 26, 0x01];
+        expected.extend_from_slice(&[0x00; 25]);
+        test_pack(big.clone(), &mut buf, &expected);
+
+        let mut expected = vec![0x0b, !26, 0xfe];
+        expected.extend_from_slice(&[0xff; 25]);
+        test_pack(-big, &mut buf, &expected);
+    }
+
+    #[test]
+    fn test_pack_unpack_bigint() {
+        let mut buf = Vec::new();
+        test_pack_unpack(BigInt::from(0), &mut buf);
+        test_pack_unpack(BigInt::from(1) << 1000, &mut buf);
+        test_pack_unpack(-(BigInt::from(1) << 1000), &mut buf);
+        test_pack_unpack(BigUint::from(1u8) << 1000, &mut buf);
+        for _ in 0..10000 {
+            let val = random::<i128>();
+            test_pack_unpack(BigInt::from(val) * BigInt::from(val), &mut buf);
+            test_pack_unpack(BigInt::from(val) << (random::<usize>() % 64), &mut buf);
+
+            // Agrees with the fixed size integers
+            let mut expected = Vec::new();
+            val.pack(&mut expected, false);
+            test_pack(BigInt::from(val), &mut buf, &expected);
+            let (out, _) = BigInt::unpack(&expected, false).unwrap();
+            assert_eq!(out, BigInt::from(val));
+        }
+    }
+}
@@ -9,8 +9,8 @@ impl Pack for bool {
     }
 }
 
-impl Unpack for bool {
-    fn unpack(inp: &[u8], _nested: bool) -> Result<(Self, &[u8]), UnpackError> {
+impl<'de> Unpack<'de> for bool {
+    fn unpack(inp: &'de [u8], _nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
         if let Some((&code, inp)) = inp.split_first() {
             if code == TRUE_CODE {
                 Ok((true, inp))
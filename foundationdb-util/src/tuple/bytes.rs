@@ -1,4 +1,6 @@
 use crate::tuple::{expect, Pack, Unpack, UnpackError};
+use std::borrow::Cow;
+use std::str;
 
 const BYTES_CODE: u8 = 0x01;
 const STRING_CODE: u8 = 0x02;
@@ -10,43 +12,73 @@ fn pack_bytes(inp: &[u8], out: &mut Vec<u8>) {
             out.push(0xFF);
         }
     }
+    out.push(0x00);
 }
 
-fn unpack_bytes(inp: &[u8]) -> (Vec<u8>, &[u8]) {
-    let mut out = Vec::new();
+// Borrows the element's bytes from the input, unless they hold escaped nulls
+fn unpack_bytes(inp: &[u8]) -> Result<(Cow<[u8]>, &[u8]), UnpackError> {
+    let mut escaped = false;
     let mut i = 0;
     while i < inp.len() {
         if inp[i] == 0x00 {
             if i + 1 < inp.len() && inp[i + 1] == 0xFF {
                 // escaped null
-                out.push(inp[i]);
+                escaped = true;
                 i += 2;
             } else {
                 // end of tuple element
-                i += 1;
-                break;
+                let bytes = if escaped {
+                    Cow::Owned(unescape(&inp[..i]))
+                } else {
+                    Cow::Borrowed(&inp[..i])
+                };
+                return Ok((bytes, &inp[i + 1..]));
             }
         } else {
-            out.push(inp[i]);
             i += 1;
         }
     }
 
-    (out, &inp[i..])
+    Err(UnpackError::OutOfData)
 }
 
-impl Pack for [u8] {
-    fn pack(&self, out: &mut Vec<u8>, _nested: bool) {
-        out.push(BYTES_CODE);
-        pack_bytes(self, out);
+fn unescape(inp: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(inp.len());
+    let mut iter = inp.iter();
+    while let Some(&b) = iter.next() {
+        out.push(b);
+        if b == 0x00 {
+            // skip the 0xFF
+            iter.next();
+        }
     }
+    out
 }
 
-impl Unpack for Vec<u8> {
-    fn unpack(inp: &[u8], _nested: bool) -> Result<(Self, &[u8]), UnpackError> {
-        let inp = expect(inp, BYTES_CODE)?;
-        let (vec, inp) = unpack_bytes(inp);
-        Ok((vec, inp))
+// Slices and vectors of u8 are byte strings rather than tuples of integers; see the u8 impls
+pub(crate) fn pack_byte_string(inp: &[u8], out: &mut Vec<u8>) {
+    out.push(BYTES_CODE);
+    pack_bytes(inp, out);
+}
+
+pub(crate) fn unpack_byte_string(inp: &[u8]) -> Result<(Cow<[u8]>, &[u8]), UnpackError> {
+    let inp = expect(inp, BYTES_CODE)?;
+    unpack_bytes(inp)
+}
+
+impl<'de: 'a, 'a> Unpack<'de> for &'a [u8] {
+    fn unpack(inp: &'de [u8], _nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
+        match unpack_byte_string(inp)? {
+            (Cow::Borrowed(bytes), inp) => Ok((bytes, inp)),
+            (Cow::Owned(_), _) => Err(UnpackError::EscapedNull),
+        }
+    }
+}
+
+impl<'de: 'a, 'a> Unpack<'de> for Cow<'a, [u8]> {
+    fn unpack(inp: &'de [u8], _nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
+        let (bytes, inp) = unpack_byte_string(inp)?;
+        Ok((bytes, inp))
     }
 }
 
@@ -57,20 +89,94 @@ impl Pack for str {
     }
 }
 
-impl Unpack for String {
-    fn unpack(inp: &[u8], _nested: bool) -> Result<(Self, &[u8]), UnpackError> {
-        let inp = expect(inp, STRING_CODE)?;
-        let (vec, inp) = unpack_bytes(inp);
-        let s = String::from_utf8(vec)?;
+impl Pack for String {
+    fn pack(&self, out: &mut Vec<u8>, nested: bool) {
+        self.as_str().pack(out, nested)
+    }
+}
+
+fn unpack_string(inp: &[u8]) -> Result<(Cow<str>, &[u8]), UnpackError> {
+    let inp = expect(inp, STRING_CODE)?;
+    let (bytes, inp) = unpack_bytes(inp)?;
+    let s = match bytes {
+        Cow::Borrowed(bytes) => Cow::Borrowed(str::from_utf8(bytes)?),
+        Cow::Owned(bytes) => Cow::Owned(String::from_utf8(bytes)?),
+    };
+    Ok((s, inp))
+}
+
+impl<'de> Unpack<'de> for String {
+    fn unpack(inp: &'de [u8], _nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
+        let (s, inp) = unpack_string(inp)?;
+        Ok((s.into_owned(), inp))
+    }
+}
+
+impl<'de: 'a, 'a> Unpack<'de> for &'a str {
+    fn unpack(inp: &'de [u8], _nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
+        match unpack_string(inp)? {
+            (Cow::Borrowed(s), inp) => Ok((s, inp)),
+            (Cow::Owned(_), _) => Err(UnpackError::EscapedNull),
+        }
+    }
+}
+
+impl<'de: 'a, 'a> Unpack<'de> for Cow<'a, str> {
+    fn unpack(inp: &'de [u8], _nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
+        let (s, inp) = unpack_string(inp)?;
         Ok((s, inp))
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::tuple::{Pack, Unpack};
+    use crate::tuple::test::test_pack;
+    use crate::tuple::{Pack, Unpack, UnpackError};
     use rand::distributions::{Alphanumeric, Standard};
     use rand::{thread_rng, Rng};
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_pack_bytes() {
+        let mut buf = Vec::new();
+        test_pack(&b"foo\x00bar"[..], &mut buf, b"\x01foo\x00\xffbar\x00");
+        test_pack(&b""[..], &mut buf, b"\x01\x00");
+        test_pack("F\u{d4}O\u{0}bar", &mut buf, b"\x02F\xc3\x94O\x00\xffbar\x00");
+        test_pack(("a", 1), &mut buf, b"\x02a\x00\x15\x01");
+    }
+
+    #[test]
+    fn test_unpack_unterminated() {
+        let r = <Vec<u8> as Unpack>::unpack(b"\x01foo", false);
+        assert_eq!(r, Err(UnpackError::OutOfData));
+    }
+
+    #[test]
+    fn test_unpack_borrowed() {
+        let buf = b"\x01foo\x00\x02bar\x00";
+        let (bytes, rest) = <&[u8]>::unpack(buf, false).unwrap();
+        assert_eq!(bytes, b"foo");
+        assert_eq!(bytes.as_ptr(), buf[1..].as_ptr());
+        let (s, rest) = <&str>::unpack(rest, false).unwrap();
+        assert_eq!(s, "bar");
+        assert!(rest.is_empty());
+
+        let (tuple, _) = <(&[u8], Cow<str>)>::unpack(buf, false).unwrap();
+        assert_eq!(tuple, (&b"foo"[..], Cow::Borrowed("bar")));
+
+        // Escaped nulls have to be copied out
+        let buf = b"\x02a\x00\xffb\x00";
+        assert_eq!(<&str>::unpack(buf, false), Err(UnpackError::EscapedNull));
+        let (s, _) = <Cow<str>>::unpack(buf, false).unwrap();
+        assert_eq!(s, Cow::<str>::Owned("a\u{0}b".to_string()));
+        let (bytes, _) = <Cow<[u8]>>::unpack(b"\x01\x00\xff\x00", false).unwrap();
+        assert_eq!(bytes, Cow::<[u8]>::Owned(vec![0]));
+
+        assert_eq!(
+            <&str>::unpack(b"\x02\xff\x00", false),
+            Err(UnpackError::BadEncoding)
+        );
+    }
 
     #[test]
     fn test_pack_unpack_bytes() {
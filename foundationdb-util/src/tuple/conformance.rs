@@ -0,0 +1,184 @@
+// Encodings that the official bindings (Python, Java, Go) produce, mostly taken from their tuple
+// tests and the tuple layer spec. Every element type is checked both as its Rust type and as an
+// Element, so that keys written by any binding read back the same here, and vice versa.
+
+use crate::tuple::{Element, Pack, Unpack, Versionstamp};
+use std::f64;
+use std::fmt::Debug;
+
+// Packs value as the root of a key and checks the bytes, then unpacks them again
+fn check_typed<T>(value: T, expected: &[u8])
+where
+    T: Pack + for<'de> Unpack<'de> + Debug + PartialEq,
+{
+    let mut buf = Vec::new();
+    value.pack(&mut buf, false);
+    assert_eq!(buf, expected, "packing {:?}", value);
+
+    let (out, rest) = T::unpack(expected, false).unwrap();
+    assert_eq!(out, value);
+    assert!(rest.is_empty());
+}
+
+// Also checks that the bytes unpack to elements which pack back to the same bytes
+fn check<T>(value: T, expected: &[u8])
+where
+    T: Pack + for<'de> Unpack<'de> + Debug + PartialEq,
+{
+    check_typed(value, expected);
+
+    let (elements, rest) = Vec::<Element>::unpack(expected, false).unwrap();
+    assert!(rest.is_empty());
+    let mut buf = Vec::new();
+    elements.pack(&mut buf, false);
+    assert_eq!(buf, expected, "packing {:?}", elements);
+}
+
+#[test]
+fn test_conformance_null() {
+    check((None::<i64>,), b"\x00");
+    check((None::<i64>, None::<i64>), b"\x00\x00");
+    check(((None::<i64>,),), b"\x05\x00\xff\x00");
+    check((((None::<i64>,),),), b"\x05\x05\x00\xff\x00\x00");
+    check(((None::<i64>, 1),), b"\x05\x00\xff\x15\x01\x00");
+}
+
+#[test]
+fn test_conformance_bytes() {
+    check((b"".to_vec(),), b"\x01\x00");
+    check((b"foo\x00bar".to_vec(),), b"\x01foo\x00\xffbar\x00");
+    check((b"\x00".to_vec(),), b"\x01\x00\xff\x00");
+    check((b"\x00\xff".to_vec(),), b"\x01\x00\xff\xff\x00");
+    check((b"\x01\x02\x03".to_vec(),), b"\x01\x01\x02\x03\x00");
+}
+
+#[test]
+fn test_conformance_string() {
+    check(("".to_string(),), b"\x02\x00");
+    check(("hello".to_string(),), b"\x02hello\x00");
+    check(
+        ("F\u{d4}O\u{0}bar".to_string(),),
+        b"\x02F\xc3\x94O\x00\xffbar\x00",
+    );
+    check(("\u{1f4a9}".to_string(),), b"\x02\xf0\x9f\x92\xa9\x00");
+}
+
+#[test]
+fn test_conformance_nested() {
+    check(((),), b"\x05\x00");
+    check(
+        ((b"foo\x00bar".to_vec(), None::<i64>, ()),),
+        b"\x05\x01foo\x00\xffbar\x00\x00\xff\x05\x00\x00",
+    );
+    check(((1, (2,)),), b"\x05\x15\x01\x05\x15\x02\x00\x00");
+    check(
+        (("a".to_string(),), ("b".to_string(),)),
+        b"\x05\x02a\x00\x00\x05\x02b\x00\x00",
+    );
+}
+
+#[test]
+fn test_conformance_int() {
+    check((0,), b"\x14");
+    check((1,), b"\x15\x01");
+    check((-1,), b"\x13\xfe");
+    check((255,), b"\x15\xff");
+    check((-255,), b"\x13\x00");
+    check((256,), b"\x16\x01\x00");
+    check((-256,), b"\x12\xfe\xff");
+    check((65536,), b"\x17\x01\x00\x00");
+    check((-5_551_212,), b"\x11\xabK\x93");
+    check((i64::max_value(),), b"\x1c\x7f\xff\xff\xff\xff\xff\xff\xff");
+    check((i64::min_value(),), b"\x0c\x7f\xff\xff\xff\xff\xff\xff\xff");
+    check(
+        (i64::min_value() + 1,),
+        b"\x0c\x80\x00\x00\x00\x00\x00\x00\x00",
+    );
+
+    // Beyond i64, the 8 byte codes still cover magnitudes below 2^64 - 1, after which a length
+    // byte follows the code
+    check_typed((1u64 << 63,), b"\x1c\x80\x00\x00\x00\x00\x00\x00\x00");
+    check_typed(
+        (-(1i128 << 63) - 1,),
+        b"\x0c\x7f\xff\xff\xff\xff\xff\xff\xfe",
+    );
+    check_typed(
+        (u64::max_value(),),
+        b"\x1d\x08\xff\xff\xff\xff\xff\xff\xff\xff",
+    );
+    check_typed(
+        (-i128::from(u64::max_value()),),
+        b"\x0b\xf7\x00\x00\x00\x00\x00\x00\x00\x00",
+    );
+    check_typed(
+        (1i128 << 64,),
+        b"\x1d\x09\x01\x00\x00\x00\x00\x00\x00\x00\x00",
+    );
+    check_typed(
+        (-(1i128 << 64),),
+        b"\x0b\xf6\xfe\xff\xff\xff\xff\xff\xff\xff\xff",
+    );
+}
+
+#[test]
+fn test_conformance_float() {
+    check((0f32,), b"\x20\x80\x00\x00\x00");
+    check((1f32,), b"\x20\xbf\x80\x00\x00");
+    check((-1f32,), b"\x20\x40\x7f\xff\xff");
+    check((-42f32,), b"\x20\x3d\xd7\xff\xff");
+
+    check((0f64,), b"\x21\x80\x00\x00\x00\x00\x00\x00\x00");
+    check((1f64,), b"\x21\xbf\xf0\x00\x00\x00\x00\x00\x00");
+    check((-42f64,), b"\x21\x3f\xba\xff\xff\xff\xff\xff\xff");
+    check((f64::INFINITY,), b"\x21\xff\xf0\x00\x00\x00\x00\x00\x00");
+    check(
+        (f64::NEG_INFINITY,),
+        b"\x21\x00\x0f\xff\xff\xff\xff\xff\xff",
+    );
+
+    // -0.0 == 0.0, so only the packing can tell them apart
+    let mut buf = Vec::new();
+    (-0f64,).pack(&mut buf, false);
+    assert_eq!(buf, b"\x21\x7f\xff\xff\xff\xff\xff\xff\xff");
+}
+
+#[test]
+fn test_conformance_bool() {
+    check((false,), b"\x26");
+    check((true,), b"\x27");
+    check(((true, false),), b"\x05\x27\x26\x00");
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn test_conformance_uuid() {
+    use uuid::Uuid;
+
+    check(
+        (Uuid::parse_str("00112233-4455-6677-8899-aabbccddeeff").unwrap(),),
+        b"\x30\x00\x11\x22\x33\x44\x55\x66\x77\x88\x99\xaa\xbb\xcc\xdd\xee\xff",
+    );
+}
+
+#[test]
+fn test_conformance_versionstamp() {
+    check(
+        (Versionstamp::complete(
+            *b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09",
+            0x0a0b,
+        ),),
+        b"\x33\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b",
+    );
+    check(
+        (Versionstamp::incomplete(5),),
+        b"\x33\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\x00\x05",
+    );
+}
+
+#[test]
+fn test_conformance_mixed() {
+    check(
+        ("users".to_string(), 42, None::<i64>, (true, None::<i64>)),
+        b"\x02users\x00\x15\x2a\x00\x05\x27\x00\xff\x00",
+    );
+}
@@ -0,0 +1,641 @@
+use crate::tuple::{expect, SerdeError, Unpack, UnpackError, Versionstamp};
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::fmt::Display;
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
+
+const NULL_CODE: u8 = 0x00;
+const NESTED_CODE: u8 = 0x05;
+
+pub fn from_slice<'de, T>(inp: &'de [u8]) -> Result<T, SerdeError>
+where
+    T: Deserialize<'de>,
+{
+    let mut de = Deserializer::new(inp);
+    let value = T::deserialize(&mut de)?;
+    de.end()?;
+    Ok(value)
+}
+
+impl de::Error for SerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerdeError::Message(msg.to_string())
+    }
+}
+
+/*
+ * Deserializer
+ */
+
+pub struct Deserializer<'de> {
+    inp: &'de [u8],
+    nested: bool,
+}
+
+impl<'de> Deserializer<'de> {
+    // Reads a value from the root of the tuple in inp
+    pub fn new(inp: &'de [u8]) -> Self {
+        Deserializer { inp, nested: false }
+    }
+
+    // Checks that all of the input was used
+    pub fn end(&self) -> Result<(), SerdeError> {
+        if self.inp.is_empty() {
+            Ok(())
+        } else {
+            Err(SerdeError::Unpack(UnpackError::TrailingData))
+        }
+    }
+
+    fn unpack<T: Unpack<'de>>(&mut self) -> Result<T, SerdeError> {
+        let (value, inp) = T::unpack(self.inp, self.nested)?;
+        self.inp = inp;
+        Ok(value)
+    }
+
+    fn peek(&self) -> Result<u8, SerdeError> {
+        match self.inp.first() {
+            Some(&code) => Ok(code),
+            None => Err(SerdeError::Unpack(UnpackError::OutOfData)),
+        }
+    }
+
+    // Reads the elements of a tuple, which is wrapped in the nested code and a terminator
+    // unless at the root
+    fn tuple<F, T>(&mut self, f: F) -> Result<T, SerdeError>
+    where
+        F: FnOnce(Elements<'_, 'de>) -> Result<T, SerdeError>,
+    {
+        let wrapped = self.nested;
+        if wrapped {
+            self.inp = expect(self.inp, NESTED_CODE)?;
+        }
+        self.nested = true;
+        let value = f(Elements { de: self, wrapped })?;
+        if wrapped {
+            self.inp = expect(self.inp, 0x00)?;
+        }
+        Ok(value)
+    }
+
+    fn integer<V: Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value, SerdeError> {
+        match i64::unpack(self.inp, self.nested) {
+            Ok((v, inp)) => {
+                self.inp = inp;
+                visitor.visit_i64(v)
+            }
+            Err(UnpackError::OutOfRange) => match u64::unpack(self.inp, self.nested) {
+                Ok((v, inp)) => {
+                    self.inp = inp;
+                    visitor.visit_u64(v)
+                }
+                Err(UnpackError::OutOfRange) => match i128::unpack(self.inp, self.nested) {
+                    Ok((v, inp)) => {
+                        self.inp = inp;
+                        visitor.visit_i128(v)
+                    }
+                    Err(UnpackError::OutOfRange) => {
+                        let v = self.unpack::<u128>()?;
+                        visitor.visit_u128(v)
+                    }
+                    Err(err) => Err(err.into()),
+                },
+                Err(err) => Err(err.into()),
+            },
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+macro_rules! deserialize_unpack {
+    ( $($method:ident => $visit:ident($ty:ty)),* ) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+                let v = self.unpack::<$ty>()?;
+                visitor.$visit(v)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = SerdeError;
+
+    // The root is always a tuple. Within it, elements are read according to their type code.
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        if !self.nested {
+            return self.tuple(|elements| visitor.visit_seq(elements));
+        }
+
+        match self.peek()? {
+            NULL_CODE => self.deserialize_option(visitor),
+            0x01 => self.deserialize_bytes(visitor),
+            0x02 => self.deserialize_str(visitor),
+            NESTED_CODE => self.tuple(|elements| visitor.visit_seq(elements)),
+            0x0b..=0x1d => self.integer(visitor),
+            0x20 => self.deserialize_f32(visitor),
+            0x21 => self.deserialize_f64(visitor),
+            0x26 | 0x27 => self.deserialize_bool(visitor),
+            #[cfg(feature = "uuid")]
+            0x30 => {
+                let v = self.unpack::<Uuid>()?;
+                visitor.visit_bytes(v.as_bytes())
+            }
+            0x33 => {
+                let v = self.unpack::<Versionstamp>()?;
+                visitor.visit_bytes(v.as_bytes())
+            }
+            _ => Err(SerdeError::Unpack(UnpackError::WrongCode)),
+        }
+    }
+
+    deserialize_unpack! {
+        deserialize_bool => visit_bool(bool),
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_u128 => visit_u128(u128),
+        deserialize_f32 => visit_f32(f32),
+        deserialize_f64 => visit_f64(f64),
+        deserialize_string => visit_string(String),
+        deserialize_byte_buf => visit_byte_buf(Vec<u8>)
+    }
+
+    // Strings and bytes are borrowed from the input where possible
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.unpack::<Cow<'de, str>>()? {
+            Cow::Borrowed(v) => visitor.visit_borrowed_str(v),
+            Cow::Owned(v) => visitor.visit_string(v),
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.unpack::<Cow<'de, [u8]>>()? {
+            Cow::Borrowed(v) => visitor.visit_borrowed_bytes(v),
+            Cow::Owned(v) => visitor.visit_byte_buf(v),
+        }
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        let s = self.unpack::<String>()?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(SerdeError::Message(format!(
+                "expected a character, got {:?}",
+                s
+            ))),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        if self.peek()? == NULL_CODE {
+            self.inp = &self.inp[1..];
+            // Within a nested tuple, nulls may be escaped like in byte strings
+            if self.nested && self.inp.first() == Some(&0xff) {
+                self.inp = &self.inp[1..];
+            }
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        self.unpack::<()>()?;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        self.tuple(|elements| visitor.visit_seq(elements))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        self.tuple(|elements| visitor.visit_seq(elements))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        self.tuple(|elements| visitor.visit_seq(elements))
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        self.tuple(|elements| visitor.visit_map(elements))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        self.tuple(|elements| visitor.visit_seq(elements))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        let nested = self.nested;
+        let wrapped = nested && self.inp.first() == Some(&NESTED_CODE);
+        if wrapped {
+            self.inp = &self.inp[1..];
+        }
+        self.nested = true;
+        let value = visitor.visit_enum(Variant {
+            de: self,
+            nested,
+            wrapped,
+        })?;
+        if wrapped {
+            self.inp = expect(self.inp, 0x00)?;
+        }
+        Ok(value)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/*
+ * Elements
+ */
+
+// The elements of a tuple being deserialized. Maps alternate keys and values.
+struct Elements<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    // Whether the tuple ends at a terminator rather than at the end of the input
+    wrapped: bool,
+}
+
+impl<'a, 'de> Elements<'a, 'de> {
+    fn at_end(&self) -> Result<bool, SerdeError> {
+        let inp = self.de.inp;
+        match inp.first() {
+            None if self.wrapped => Err(SerdeError::Unpack(UnpackError::OutOfData)),
+            None => Ok(true),
+            // A null followed by 0xff is an escaped null element
+            Some(&0x00) if self.wrapped => Ok(inp.get(1) != Some(&0xff)),
+            Some(_) => Ok(false),
+        }
+    }
+}
+
+impl<'a, 'de> de::SeqAccess<'de> for Elements<'a, 'de> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, SerdeError> {
+        if self.at_end()? {
+            Ok(None)
+        } else {
+            seed.deserialize(&mut *self.de).map(Some)
+        }
+    }
+}
+
+impl<'a, 'de> de::MapAccess<'de> for Elements<'a, 'de> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SerdeError> {
+        if self.at_end()? {
+            Ok(None)
+        } else {
+            seed.deserialize(&mut *self.de).map(Some)
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SerdeError> {
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+/*
+ * Variant
+ */
+
+// An enum variant: its name, followed by its fields if it has any. Within a tuple, variants with
+// fields are wrapped in a nested tuple.
+struct Variant<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    nested: bool,
+    wrapped: bool,
+}
+
+impl<'a, 'de> Variant<'a, 'de> {
+    fn expect_fields(&self) -> Result<(), SerdeError> {
+        if self.wrapped || !self.nested {
+            Ok(())
+        } else {
+            Err(SerdeError::Unpack(UnpackError::BadEncoding))
+        }
+    }
+}
+
+impl<'a, 'de> de::EnumAccess<'de> for Variant<'a, 'de> {
+    type Error = SerdeError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), SerdeError> {
+        let tag = self.de.unpack::<Cow<'de, str>>()?;
+        let value = seed.deserialize(IntoDeserializer::<SerdeError>::into_deserializer(tag))?;
+        Ok((value, self))
+    }
+}
+
+impl<'a, 'de> de::VariantAccess<'de> for Variant<'a, 'de> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        if self.wrapped {
+            Err(SerdeError::Unpack(UnpackError::BadEncoding))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, SerdeError> {
+        self.expect_fields()?;
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        self.expect_fields()?;
+        visitor.visit_seq(Elements {
+            de: self.de,
+            wrapped: self.wrapped,
+        })
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        self.expect_fields()?;
+        visitor.visit_seq(Elements {
+            de: self.de,
+            wrapped: self.wrapped,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::from_slice;
+    use crate::tuple::{to_vec, Element, Pack, SerdeError, UnpackError};
+    use serde::de::{self, Deserialize, Deserializer, Visitor};
+    use serde_derive::{Deserialize, Serialize};
+    use std::borrow::Cow;
+    use std::collections::BTreeMap;
+    use std::fmt::{self, Debug, Formatter};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct UserKey {
+        name: String,
+        id: i64,
+        deleted: Option<bool>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Id(u32);
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Borrowed<'a> {
+        name: &'a str,
+        #[serde(borrow)]
+        tag: Cow<'a, str>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Event {
+        Created,
+        Moved(i64),
+        Swapped(i64, i64),
+        Renamed { from: String },
+    }
+
+    fn round_trip<T>(value: T)
+    where
+        T: serde::Serialize + for<'de> Deserialize<'de> + Debug + PartialEq,
+    {
+        let packed = to_vec(&value).unwrap();
+        assert_eq!(from_slice::<T>(&packed), Ok(value));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let key = || UserKey {
+            name: "a\u{0}b".to_string(),
+            id: -5,
+            deleted: Some(true),
+        };
+        round_trip(key());
+        round_trip((1, key()));
+        round_trip(Id(7));
+        round_trip((Id(7), 'x', 2.5f64, u64::max_value(), i128::min_value()));
+        round_trip(None::<u32>);
+        round_trip(vec![vec![1, 2], vec![], vec![3]]);
+        round_trip(((), ((),), Some(())));
+
+        let mut map = BTreeMap::new();
+        map.insert("a".to_string(), vec![1]);
+        map.insert("b".to_string(), vec![]);
+        round_trip((map.clone(), 1));
+        round_trip(map);
+    }
+
+    #[test]
+    fn test_round_trip_enum() {
+        round_trip(Event::Created);
+        round_trip(Event::Moved(1));
+        round_trip(Event::Swapped(1, 2));
+        round_trip(Event::Renamed {
+            from: "a".to_string(),
+        });
+        round_trip((
+            Event::Swapped(1, 2),
+            Event::Created,
+            Event::Renamed {
+                from: "a".to_string(),
+            },
+        ));
+    }
+
+    #[test]
+    fn test_from_slice_packed() {
+        let mut packed = Vec::new();
+        ("a", 1, (true, -1)).pack(&mut packed, false);
+        assert_eq!(
+            from_slice::<(String, u8, (bool, i8))>(&packed),
+            Ok(("a".to_string(), 1, (true, -1)))
+        );
+        assert_eq!(
+            from_slice::<(String, u8)>(&packed),
+            Err(SerdeError::Unpack(UnpackError::TrailingData))
+        );
+        assert_eq!(
+            from_slice::<(String, String)>(&packed),
+            Err(SerdeError::Unpack(UnpackError::WrongCode))
+        );
+        assert_eq!(
+            from_slice::<(u8, u8)>(b"\x15\x01\x16\x01\x00"),
+            Err(SerdeError::Unpack(UnpackError::OutOfRange))
+        );
+        assert!(from_slice::<Event>(b"\x02Unknown\x00").is_err());
+        // Within a tuple, variants with fields must be nested
+        assert!(from_slice::<(Event,)>(b"\x02Moved\x00\x15\x01").is_err());
+    }
+
+    #[test]
+    fn test_from_slice_borrowed() {
+        let packed = b"\x02a\x00\x02b\x00";
+        let borrowed: Borrowed = from_slice(packed).unwrap();
+        assert_eq!(borrowed.name, "a");
+        if let Cow::Owned(_) = borrowed.tag {
+            panic!("tag was copied");
+        }
+        assert_eq!(borrowed.tag, "b");
+
+        // Escaped nulls have to be copied out
+        let packed = b"\x02a\x00\x02\x00\xff\x00";
+        let borrowed: Borrowed = from_slice(packed).unwrap();
+        assert_eq!(borrowed.tag, "\u{0}");
+        assert!(from_slice::<(&str, &str)>(packed).is_err());
+    }
+
+    // Collects whatever deserialize_any finds into Elements
+    struct Any(Element);
+
+    impl<'de> Deserialize<'de> for Any {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_any(AnyVisitor).map(Any)
+        }
+    }
+
+    struct AnyVisitor;
+
+    impl<'de> Visitor<'de> for AnyVisitor {
+        type Value = Element;
+
+        fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+            write!(f, "any tuple element")
+        }
+
+        fn visit_none<E>(self) -> Result<Element, E> {
+            Ok(Element::Null)
+        }
+
+        fn visit_bool<E>(self, v: bool) -> Result<Element, E> {
+            Ok(Element::Bool(v))
+        }
+
+        fn visit_i64<E>(self, v: i64) -> Result<Element, E> {
+            Ok(Element::Int(v))
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<Element, E> {
+            Err(E::custom(format!("{} is too large", v)))
+        }
+
+        fn visit_f64<E>(self, v: f64) -> Result<Element, E> {
+            Ok(Element::Double(v))
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Element, E> {
+            Ok(Element::String(v.to_string()))
+        }
+
+        fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Element, E> {
+            Ok(Element::Bytes(v))
+        }
+
+        fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Element, A::Error> {
+            let mut elements = Vec::new();
+            while let Some(Any(element)) = seq.next_element()? {
+                elements.push(element);
+            }
+            Ok(Element::Nested(elements))
+        }
+    }
+
+    #[test]
+    fn test_deserialize_any() {
+        let mut packed = Vec::new();
+        (b"\x00"[..].to_vec(), "a", -1, (true, 2.5f64), None::<u8>).pack(&mut packed, false);
+        let Any(element) = from_slice(&packed).unwrap();
+        assert_eq!(
+            element,
+            Element::Nested(vec![
+                Element::Bytes(vec![0]),
+                Element::String("a".to_string()),
+                Element::Int(-1),
+                Element::Nested(vec![Element::Bool(true), Element::Double(2.5)]),
+                Element::Null,
+            ])
+        );
+
+        assert!(from_slice::<Any>(b"\x1d\x09\x01\x00\x00\x00\x00\x00\x00\x00\x00").is_err());
+    }
+}
@@ -0,0 +1,267 @@
+// Support code for the code generated by #[derive(Pack, Unpack)]. Not a stable API.
+
+use crate::tuple::{expect, Unpack, UnpackError};
+use std::borrow::Cow;
+
+const NESTED_CODE: u8 = 0x05;
+
+pub fn begin(out: &mut Vec<u8>, nested: bool) {
+    if nested {
+        out.push(NESTED_CODE);
+    }
+}
+
+pub fn end(out: &mut Vec<u8>, nested: bool) {
+    if nested {
+        out.push(0x00);
+    }
+}
+
+pub fn expect_begin(inp: &[u8], nested: bool) -> Result<&[u8], UnpackError> {
+    if nested {
+        expect(inp, NESTED_CODE)
+    } else {
+        Ok(inp)
+    }
+}
+
+pub fn expect_end(inp: &[u8], nested: bool) -> Result<&[u8], UnpackError> {
+    if nested {
+        expect(inp, 0x00)
+    } else {
+        Ok(inp)
+    }
+}
+
+// Reads an enum variant's tag, along with whether it began a nested tuple holding the
+// variant's fields
+pub fn unpack_tag(inp: &[u8], nested: bool) -> Result<(Cow<str>, bool, &[u8]), UnpackError> {
+    let (wrapped, inp) = match inp.first() {
+        Some(&NESTED_CODE) if nested => (true, &inp[1..]),
+        _ => (false, inp),
+    };
+    let (tag, inp) = Cow::unpack(inp, true)?;
+    Ok((tag, wrapped, inp))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tuple::test::{test_pack, test_pack_unpack};
+    use crate::tuple::{Pack, Unpack, UnpackError};
+    use crate::Subspace;
+    use std::borrow::Cow;
+
+    #[derive(Debug, PartialEq, Pack, Unpack)]
+    struct UserKey {
+        name: String,
+        id: i64,
+    }
+
+    #[derive(Debug, PartialEq, Pack, Unpack)]
+    struct Pair(bool, Option<u32>);
+
+    #[derive(Debug, PartialEq, Pack, Unpack)]
+    struct Unit;
+
+    #[derive(Debug, PartialEq, Pack, Unpack)]
+    struct Cached {
+        id: i64,
+        #[tuple(skip)]
+        hits: u32,
+    }
+
+    #[derive(Debug, PartialEq, Pack, Unpack)]
+    struct Indexed {
+        index: String,
+        #[tuple(flatten)]
+        user: UserKey,
+    }
+
+    #[derive(Debug, PartialEq, Pack, Unpack)]
+    struct FlatPair {
+        #[tuple(flatten)]
+        pair: Pair,
+    }
+
+    #[derive(Debug, PartialEq, Pack, Unpack)]
+    struct FlatOpt {
+        #[tuple(flatten)]
+        pair: Option<(bool, i64)>,
+    }
+
+    #[derive(Debug, PartialEq, Pack, Unpack)]
+    struct Wrapper<T> {
+        inner: T,
+    }
+
+    #[derive(Debug, PartialEq, Pack, Unpack)]
+    struct Borrowed<'a> {
+        name: &'a str,
+        tag: Cow<'a, [u8]>,
+    }
+
+    #[derive(Debug, PartialEq, Pack, Unpack)]
+    enum Event {
+        Created,
+        #[tuple(rename = "del")]
+        Deleted,
+        Renamed {
+            from: String,
+            to: String,
+        },
+        Moved(i64),
+    }
+
+    #[test]
+    fn test_pack_struct() {
+        let mut buf = Vec::new();
+        let key = UserKey {
+            name: "a".to_string(),
+            id: 1,
+        };
+        test_pack(&key, &mut buf, b"\x02a\x00\x15\x01");
+        test_pack((&key,), &mut buf, b"\x05\x02a\x00\x15\x01\x00");
+        test_pack(Pair(true, None), &mut buf, b"\x27\x00");
+        test_pack(Unit, &mut buf, b"");
+        test_pack(Cached { id: 1, hits: 7 }, &mut buf, b"\x15\x01");
+        test_pack(
+            Indexed {
+                index: "i".to_string(),
+                user: key,
+            },
+            &mut buf,
+            b"\x02i\x00\x02a\x00\x15\x01",
+        );
+
+        // Nulls are escaped within nested tuples, flattened fields included
+        test_pack((Pair(true, None),), &mut buf, b"\x05\x27\x00\xff\x00");
+        let flat = FlatPair {
+            pair: Pair(false, None),
+        };
+        test_pack(&flat, &mut buf, b"\x26\x00");
+        test_pack((&flat,), &mut buf, b"\x05\x26\x00\xff\x00");
+        test_pack(FlatOpt { pair: None }, &mut buf, b"\x00");
+        test_pack((FlatOpt { pair: None },), &mut buf, b"\x05\x00\xff\x00");
+        let flat = FlatOpt {
+            pair: Some((true, 1)),
+        };
+        test_pack(&flat, &mut buf, b"\x27\x15\x01");
+        test_pack((&flat,), &mut buf, b"\x05\x27\x15\x01\x00");
+    }
+
+    #[test]
+    fn test_pack_unpack_struct() {
+        let mut buf = Vec::new();
+        let key = || UserKey {
+            name: "a".to_string(),
+            id: -5,
+        };
+        test_pack_unpack(key(), &mut buf);
+        test_pack_unpack((1, key()), &mut buf);
+        test_pack_unpack(Pair(false, Some(3)), &mut buf);
+        test_pack_unpack((Pair(true, None), 1), &mut buf);
+        test_pack_unpack(
+            (FlatPair {
+                pair: Pair(true, None),
+            },),
+            &mut buf,
+        );
+        test_pack_unpack(FlatOpt { pair: None }, &mut buf);
+        test_pack_unpack((FlatOpt { pair: None },), &mut buf);
+        test_pack_unpack(
+            (FlatOpt {
+                pair: Some((false, -1)),
+            },),
+            &mut buf,
+        );
+        test_pack_unpack(Unit, &mut buf);
+        test_pack_unpack(
+            Wrapper {
+                inner: (1, "b".to_string()),
+            },
+            &mut buf,
+        );
+        test_pack_unpack(
+            Indexed {
+                index: "i".to_string(),
+                user: key(),
+            },
+            &mut buf,
+        );
+
+        let (cached, rest) = Cached::unpack(b"\x15\x01", false).unwrap();
+        assert_eq!(cached, Cached { id: 1, hits: 0 });
+        assert!(rest.is_empty());
+
+        let packed = b"\x02a\x00\x01b\x00";
+        let (borrowed, _) = Borrowed::unpack(packed, false).unwrap();
+        assert_eq!(borrowed.name, "a");
+        assert_eq!(borrowed.tag, Cow::Borrowed(&b"b"[..]));
+
+        let subspace = Subspace::new(&("users",));
+        let packed = subspace.pack(&key());
+        assert_eq!(subspace.unpack::<UserKey>(&packed), Ok(key()));
+    }
+
+    #[test]
+    fn test_pack_enum() {
+        let mut buf = Vec::new();
+        test_pack(Event::Created, &mut buf, b"\x02Created\x00");
+        test_pack(Event::Deleted, &mut buf, b"\x02del\x00");
+        test_pack(Event::Moved(1), &mut buf, b"\x02Moved\x00\x15\x01");
+        test_pack(
+            (Event::Moved(1), Event::Created),
+            &mut buf,
+            b"\x05\x02Moved\x00\x15\x01\x00\x02Created\x00",
+        );
+        test_pack(
+            Event::Renamed {
+                from: "a".to_string(),
+                to: "b".to_string(),
+            },
+            &mut buf,
+            b"\x02Renamed\x00\x02a\x00\x02b\x00",
+        );
+    }
+
+    #[test]
+    fn test_pack_unpack_enum() {
+        let mut buf = Vec::new();
+        test_pack_unpack(Event::Created, &mut buf);
+        test_pack_unpack(Event::Deleted, &mut buf);
+        test_pack_unpack(Event::Moved(-1), &mut buf);
+        test_pack_unpack((Event::Moved(2), Event::Deleted, 3), &mut buf);
+        test_pack_unpack(
+            (
+                Event::Renamed {
+                    from: "a".to_string(),
+                    to: "b".to_string(),
+                },
+                Event::Created,
+            ),
+            &mut buf,
+        );
+    }
+
+    #[test]
+    fn test_unpack_enum_invalid() {
+        assert_eq!(
+            Event::unpack(b"\x02Unknown\x00", false),
+            Err(UnpackError::BadEncoding)
+        );
+        // Renamed variants only answer to their new tag
+        assert_eq!(
+            Event::unpack(b"\x02Deleted\x00", false),
+            Err(UnpackError::BadEncoding)
+        );
+        // Within a tuple, variants with fields must be nested
+        assert_eq!(
+            Event::unpack(b"\x02Moved\x00\x15\x01", true),
+            Err(UnpackError::BadEncoding)
+        );
+        assert_eq!(
+            Event::unpack(b"\x15\x01", false),
+            Err(UnpackError::WrongCode)
+        );
+    }
+}
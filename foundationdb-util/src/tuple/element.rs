@@ -0,0 +1,238 @@
+use crate::tuple::{Pack, Unpack, UnpackError, Versionstamp};
+#[cfg(feature = "num-bigint")]
+use num_bigint::BigInt;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
+
+const NULL_CODE: u8 = 0x00;
+const NESTED_CODE: u8 = 0x05;
+
+// Any single tuple element, for decoding keys whose layout isn't known up front. Elements compare
+// and hash by their packed form, so they sort the same way their keys do.
+#[derive(Clone, Debug)]
+pub enum Element {
+    Null,
+    Bytes(Vec<u8>),
+    String(String),
+    Int(i64),
+    // Integers that don't fit an i64
+    #[cfg(feature = "num-bigint")]
+    BigInt(BigInt),
+    Float(f32),
+    Double(f64),
+    Bool(bool),
+    #[cfg(feature = "uuid")]
+    Uuid(Uuid),
+    Versionstamp(Versionstamp),
+    Nested(Vec<Element>),
+}
+
+impl Element {
+    fn packed(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.pack(&mut out, true);
+        out
+    }
+}
+
+impl Pack for Element {
+    fn pack(&self, out: &mut Vec<u8>, nested: bool) {
+        match self {
+            Element::Null => out.push(NULL_CODE),
+            Element::Bytes(v) => v[..].pack(out, nested),
+            Element::String(v) => v[..].pack(out, nested),
+            Element::Int(v) => v.pack(out, nested),
+            #[cfg(feature = "num-bigint")]
+            Element::BigInt(v) => v.pack(out, nested),
+            Element::Float(v) => v.pack(out, nested),
+            Element::Double(v) => v.pack(out, nested),
+            Element::Bool(v) => v.pack(out, nested),
+            #[cfg(feature = "uuid")]
+            Element::Uuid(v) => v.pack(out, nested),
+            Element::Versionstamp(v) => v.pack(out, nested),
+            Element::Nested(v) => v.pack(out, true),
+        }
+    }
+
+    fn pack_element(&self, out: &mut Vec<u8>, in_nested: bool) {
+        match self {
+            Element::Null if in_nested => out.extend_from_slice(&[NULL_CODE, 0xff]),
+            _ => self.pack(out, true),
+        }
+    }
+}
+
+impl<'de> Unpack<'de> for Element {
+    fn unpack(inp: &'de [u8], nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
+        fn map<T>(
+            res: Result<(T, &[u8]), UnpackError>,
+            f: fn(T) -> Element,
+        ) -> Result<(Element, &[u8]), UnpackError> {
+            res.map(|(v, inp)| (f(v), inp))
+        }
+
+        let code = *inp.first().ok_or(UnpackError::OutOfData)?;
+        match code {
+            // Within nested tuples, nulls are escaped
+            NULL_CODE if nested && inp.get(1) == Some(&0xff) => Ok((Element::Null, &inp[2..])),
+            NULL_CODE => Ok((Element::Null, &inp[1..])),
+            0x01 => map(Vec::unpack(inp, nested), Element::Bytes),
+            0x02 => map(String::unpack(inp, nested), Element::String),
+            NESTED_CODE => map(Vec::unpack(inp, true), Element::Nested),
+            0x0b..=0x1d => match i64::unpack(inp, nested) {
+                #[cfg(feature = "num-bigint")]
+                Err(UnpackError::OutOfRange) => map(BigInt::unpack(inp, nested), Element::BigInt),
+                res => map(res, Element::Int),
+            },
+            0x20 => map(f32::unpack(inp, nested), Element::Float),
+            0x21 => map(f64::unpack(inp, nested), Element::Double),
+            0x26 | 0x27 => map(bool::unpack(inp, nested), Element::Bool),
+            #[cfg(feature = "uuid")]
+            0x30 => map(Uuid::unpack(inp, nested), Element::Uuid),
+            0x33 => map(Versionstamp::unpack(inp, nested), Element::Versionstamp),
+            _ => Err(UnpackError::WrongCode),
+        }
+    }
+}
+
+impl PartialEq for Element {
+    fn eq(&self, other: &Self) -> bool {
+        self.packed() == other.packed()
+    }
+}
+
+impl Eq for Element {}
+
+impl PartialOrd for Element {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Element {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.packed().cmp(&other.packed())
+    }
+}
+
+impl Hash for Element {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.packed().hash(state);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Element;
+    use crate::tuple::test::{test_pack, test_pack_unpack};
+    use crate::tuple::{Pack, Unpack, Versionstamp};
+
+    #[test]
+    fn test_pack_unpack_element() {
+        let mut buf = Vec::new();
+        test_pack_unpack(
+            vec![
+                Element::Null,
+                Element::Bytes(b"\x00\xff".to_vec()),
+                Element::String("hello".to_string()),
+                Element::Int(-42),
+                Element::Int(i64::max_value()),
+                Element::Float(1.5),
+                Element::Double(-2.5),
+                Element::Bool(true),
+                Element::Versionstamp(Versionstamp::incomplete(7)),
+                Element::Nested(vec![Element::Int(1), Element::Nested(vec![])]),
+            ],
+            &mut buf,
+        );
+        test_pack_unpack(Vec::<Element>::new(), &mut buf);
+    }
+
+    #[test]
+    fn test_unpack_any_tuple() {
+        let mut buf = Vec::new();
+        ("users", 42, (true, -1.5f64), &b"id"[..]).pack(&mut buf, false);
+        let (elements, rest) = Vec::<Element>::unpack(&buf, false).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(
+            elements,
+            vec![
+                Element::String("users".to_string()),
+                Element::Int(42),
+                Element::Nested(vec![Element::Bool(true), Element::Double(-1.5)]),
+                Element::Bytes(b"id".to_vec()),
+            ]
+        );
+
+        // Nulls escaped within nested tuples, as the official bindings write them
+        let (elements, _) = Vec::<Element>::unpack(b"\x05\x00\xff\x15\x01\x00", false).unwrap();
+        assert_eq!(
+            elements,
+            vec![Element::Nested(vec![Element::Null, Element::Int(1)])]
+        );
+    }
+
+    #[test]
+    fn test_pack_element() {
+        let mut buf = Vec::new();
+        test_pack(
+            vec![
+                Element::String("a".to_string()),
+                Element::Nested(vec![Element::Int(1)]),
+            ],
+            &mut buf,
+            b"\x02a\x00\x05\x15\x01\x00",
+        );
+        test_pack(
+            vec![
+                Element::Null,
+                Element::Nested(vec![Element::Null, Element::Nested(vec![Element::Null])]),
+            ],
+            &mut buf,
+            b"\x00\x05\x00\xff\x05\x00\xff\x00\x00",
+        );
+    }
+
+    #[test]
+    fn test_element_order() {
+        let mut elements = vec![
+            Element::Nested(vec![Element::Int(1)]),
+            Element::Bool(false),
+            Element::Double(0.5),
+            Element::Int(3),
+            Element::Int(-300),
+            Element::String("b".to_string()),
+            Element::String("a".to_string()),
+            Element::Bytes(b"z".to_vec()),
+            Element::Null,
+        ];
+        elements.sort();
+        assert_eq!(
+            elements,
+            vec![
+                Element::Null,
+                Element::Bytes(b"z".to_vec()),
+                Element::String("a".to_string()),
+                Element::String("b".to_string()),
+                Element::Nested(vec![Element::Int(1)]),
+                Element::Int(-300),
+                Element::Int(3),
+                Element::Double(0.5),
+                Element::Bool(false),
+            ]
+        );
+    }
+
+    #[cfg(feature = "num-bigint")]
+    #[test]
+    fn test_unpack_bigint_element() {
+        use num_bigint::BigInt;
+
+        let mut buf = Vec::new();
+        u64::max_value().pack(&mut buf, false);
+        let (element, _) = Element::unpack(&buf, false).unwrap();
+        assert_eq!(element, Element::BigInt(BigInt::from(u64::max_value())));
+    }
+}
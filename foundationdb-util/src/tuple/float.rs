@@ -1,34 +1,135 @@
-use crate::tuple::{Pack, Unpack, UnpackError};
+use crate::tuple::{expect, Pack, Unpack, UnpackError};
+use byteorder::{BigEndian, ByteOrder};
+use std::mem::size_of;
 
-// const FLOAT_CODE: u8 = 0x20;
-// const DOUBLE_CODE: u8 = 0x21;
+const FLOAT_CODE: u8 = 0x20;
+const DOUBLE_CODE: u8 = 0x21;
 
-impl Pack for f32 {
-    fn pack(&self, _out: &mut Vec<u8>, _nested: bool) {
-        // TODO: Implement me
-        unimplemented!()
-    }
+// Floats are stored big endian, with the sign bit flipped for positive numbers and every bit
+// flipped for negative ones, so that their encodings sort in numeric order (with NaNs at the ends)
+
+macro_rules! impl_float {
+    ($ty:ty, $bits:ty, $code:expr, $write:ident, $read:ident) => {
+        impl Pack for $ty {
+            fn pack(&self, out: &mut Vec<u8>, _nested: bool) {
+                let bits = self.to_bits();
+                let sign = 1 << (size_of::<$bits>() * 8 - 1);
+                let bits = if bits & sign != 0 { !bits } else { bits ^ sign };
+
+                let mut buf = [0; size_of::<$bits>()];
+                BigEndian::$write(&mut buf, bits);
+
+                out.push($code);
+                out.extend_from_slice(&buf);
+            }
+        }
+
+        impl<'de> Unpack<'de> for $ty {
+            fn unpack(inp: &'de [u8], _nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
+                let inp = expect(inp, $code)?;
+
+                if inp.len() < size_of::<$bits>() {
+                    return Err(UnpackError::OutOfData);
+                }
+
+                let (buf, inp) = inp.split_at(size_of::<$bits>());
+                let bits = BigEndian::$read(buf);
+                let sign = 1 << (size_of::<$bits>() * 8 - 1);
+                let bits = if bits & sign != 0 { bits ^ sign } else { !bits };
+                Ok((<$ty>::from_bits(bits), inp))
+            }
+        }
+    };
 }
 
-impl Unpack for f32 {
-    fn unpack(_inp: &[u8], _nested: bool) -> Result<(Self, &[u8]), UnpackError> {
-        // TODO: Implement me
-        unimplemented!()
+impl_float!(f32, u32, FLOAT_CODE, write_u32, read_u32);
+impl_float!(f64, u64, DOUBLE_CODE, write_u64, read_u64);
+
+#[cfg(test)]
+mod test {
+    use crate::tuple::test::{test_pack, test_pack_unpack};
+    use crate::tuple::{Pack, Unpack};
+    use rand::random;
+    use std::f32;
+    use std::f64;
+
+    #[test]
+    fn test_pack_f32() {
+        let mut buf = Vec::new();
+        test_pack(0f32, &mut buf, &[0x20, 0x80, 0x00, 0x00, 0x00]);
+        test_pack(-0f32, &mut buf, &[0x20, 0x7f, 0xff, 0xff, 0xff]);
+        test_pack(1f32, &mut buf, &[0x20, 0xbf, 0x80, 0x00, 0x00]);
+        test_pack(-1f32, &mut buf, &[0x20, 0x40, 0x7f, 0xff, 0xff]);
+        test_pack(2.5f32, &mut buf, &[0x20, 0xc0, 0x20, 0x00, 0x00]);
+        test_pack(f32::INFINITY, &mut buf, &[0x20, 0xff, 0x80, 0x00, 0x00]);
+        test_pack(f32::NEG_INFINITY, &mut buf, &[0x20, 0x00, 0x7f, 0xff, 0xff]);
+        test_pack(f32::from_bits(0x7fc0_0000), &mut buf, &[0x20, 0xff, 0xc0, 0x00, 0x00]);
     }
-}
 
-impl Pack for f64 {
-    fn pack(&self, _out: &mut Vec<u8>, _nested: bool) {
-        // TODO: Implement me
-        unimplemented!()
+    #[test]
+    fn test_pack_f64() {
+        let mut buf = Vec::new();
+        test_pack(0f64, &mut buf, &[0x21, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        test_pack(-0f64, &mut buf, &[0x21, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        test_pack(1f64, &mut buf, &[0x21, 0xbf, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        test_pack(-1f64, &mut buf, &[0x21, 0x40, 0x0f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        test_pack(2.5f64, &mut buf, &[0x21, 0xc0, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        test_pack(f64::INFINITY, &mut buf, &[0x21, 0xff, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        test_pack(f64::NEG_INFINITY, &mut buf, &[0x21, 0x00, 0x0f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        test_pack(
+            f64::from_bits(0x7ff8_0000_0000_0000),
+            &mut buf,
+            &[0x21, 0xff, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        );
     }
-}
 
-impl Unpack for f64 {
-    fn unpack(_inp: &[u8], _nested: bool) -> Result<(Self, &[u8]), UnpackError> {
-        // TODO: Implement me
-        unimplemented!()
+    #[test]
+    fn test_pack_unpack_float() {
+        let mut buf = Vec::new();
+        for &val in &[0f32, -0f32, 1f32, -1f32, f32::MIN, f32::MAX, f32::MIN_POSITIVE] {
+            test_pack_unpack(val, &mut buf);
+        }
+        for &val in &[0f64, -0f64, 1f64, -1f64, f64::MIN, f64::MAX, f64::MIN_POSITIVE] {
+            test_pack_unpack(val, &mut buf);
+        }
+        for _ in 0..100000 {
+            test_pack_unpack(random::<f64>() * 2e10 - 1e10, &mut buf);
+        }
+
+        // NaN isn't equal to itself, so compare bits instead
+        buf.clear();
+        f64::NAN.pack(&mut buf, false);
+        let (out_val, rest) = f64::unpack(&buf, false).unwrap();
+        assert_eq!(out_val.to_bits(), f64::NAN.to_bits());
+        assert!(rest.is_empty());
     }
-}
 
-// TODO: Test f32 and f64
+    #[test]
+    fn test_float_order() {
+        let vals = [
+            -f64::NAN,
+            f64::NEG_INFINITY,
+            f64::MIN,
+            -1.5,
+            -f64::MIN_POSITIVE,
+            -0.0,
+            0.0,
+            f64::MIN_POSITIVE,
+            1.5,
+            f64::MAX,
+            f64::INFINITY,
+            f64::NAN,
+        ];
+        let packed = vals
+            .iter()
+            .map(|val| {
+                let mut buf = Vec::new();
+                val.pack(&mut buf, false);
+                buf
+            })
+            .collect::<Vec<_>>();
+        for pair in packed.windows(2) {
+            assert!(pair[0] < pair[1]);
+        }
+    }
+}
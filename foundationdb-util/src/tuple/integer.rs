@@ -1,120 +1,129 @@
+use crate::tuple::bytes::{pack_byte_string, unpack_byte_string};
 use crate::tuple::{Pack, Unpack, UnpackError};
-use byteorder::{BigEndian, ByteOrder};
 use std::convert::TryFrom;
 
-// This value is used (unused by us) for a 9+ byte negative integer
+// This value is used for a 9+ byte negative integer, followed by its inverted length
 const NEG_INT_START: u8 = 0x0b;
 // This value is used for exactly zero
 const INT_ZERO_CODE: u8 = 0x14;
-// This value is used (unused by us) for a 9+ byte positive integer
+// This value is used for a 9+ byte positive integer, followed by its length
 const POS_INT_END: u8 = 0x1d;
 
-fn pack_int(inp: i64, out: &mut Vec<u8>) {
-    let nbytes = ((64 - inp.wrapping_abs().leading_zeros() + 7) / 8) as usize;
-
-    let code = if inp >= 0 {
-        INT_ZERO_CODE + nbytes as u8
+// Packs an integer given as its sign and its big endian magnitude, without leading zeros. Like the
+// official bindings, the 9+ byte encodings are also used for magnitudes of exactly 2^64-1.
+pub(crate) fn pack_magnitude(negative: bool, mag: &[u8], out: &mut Vec<u8>) {
+    let len = mag.len();
+    if len == 0 {
+        out.push(INT_ZERO_CODE);
+    } else if len > 8 || (len == 8 && mag.iter().all(|&b| b == 0xff)) {
+        assert!(len <= 255, "integer too large to pack");
+        if negative {
+            out.push(NEG_INT_START);
+            out.push(!(len as u8));
+        } else {
+            out.push(POS_INT_END);
+            out.push(len as u8);
+        }
+    } else if negative {
+        out.push(INT_ZERO_CODE - len as u8);
     } else {
-        INT_ZERO_CODE - nbytes as u8
-    };
+        out.push(INT_ZERO_CODE + len as u8);
+    }
 
-    let val = if inp >= 0 {
-        inp
+    // Negative integers are stored in ones' complement, so they sort before smaller magnitudes
+    if negative {
+        out.extend(mag.iter().map(|b| !b));
     } else {
-        let max_value = 1i64
-            .checked_shl(nbytes as u32 * 8)
-            .unwrap_or(0)
-            .wrapping_sub(1);
-        max_value.wrapping_add(inp)
-    };
+        out.extend_from_slice(mag);
+    }
+}
 
-    let mut buf = [0; 8];
-    BigEndian::write_i64(&mut buf, val);
+// Unpacks an integer into its sign and its encoded bytes, which are the ones' complement of the
+// magnitude for negative integers
+pub(crate) fn unpack_raw(inp: &[u8]) -> Result<(bool, &[u8], &[u8]), UnpackError> {
+    let (&code, inp) = inp.split_first().ok_or(UnpackError::OutOfData)?;
 
-    out.push(code);
-    out.extend_from_slice(&buf[8 - nbytes..]);
-}
+    let (negative, len, inp) = match code {
+        NEG_INT_START => {
+            let (&len, inp) = inp.split_first().ok_or(UnpackError::OutOfData)?;
+            (true, usize::from(!len), inp)
+        }
+        POS_INT_END => {
+            let (&len, inp) = inp.split_first().ok_or(UnpackError::OutOfData)?;
+            (false, usize::from(len), inp)
+        }
+        _ if (NEG_INT_START + 1..INT_ZERO_CODE).contains(&code) => {
+            (true, usize::from(INT_ZERO_CODE - code), inp)
+        }
+        _ if (INT_ZERO_CODE..POS_INT_END).contains(&code) => {
+            (false, usize::from(code - INT_ZERO_CODE), inp)
+        }
+        _ => return Err(UnpackError::WrongCode),
+    };
 
-fn pack_uint(inp: u64, out: &mut Vec<u8>) {
-    let nbytes = ((64 - inp.leading_zeros() + 7) / 8) as usize;
+    if inp.len() < len {
+        return Err(UnpackError::OutOfData);
+    }
 
-    let code = INT_ZERO_CODE + nbytes as u8;
+    let (raw, inp) = inp.split_at(len);
+    Ok((negative, raw, inp))
+}
 
-    let mut buf = [0; 8];
-    BigEndian::write_u64(&mut buf, inp);
+fn pack_int(inp: i128, out: &mut Vec<u8>) {
+    // Also right for i128::MIN, whose magnitude only fits unsigned
+    let mag = if inp < 0 {
+        (inp as u128).wrapping_neg()
+    } else {
+        inp as u128
+    };
+    pack_uint(inp < 0, mag, out);
+}
 
-    out.push(code);
-    out.extend_from_slice(&buf[8 - nbytes..]);
+fn pack_uint(negative: bool, inp: u128, out: &mut Vec<u8>) {
+    let buf = inp.to_be_bytes();
+    let start = buf.iter().position(|&b| b != 0).unwrap_or(buf.len());
+    pack_magnitude(negative, &buf[start..], out);
 }
 
-fn unpack_int(inp: &[u8]) -> Result<(i64, &[u8]), UnpackError> {
-    if let Some((&code, inp)) = inp.split_first() {
-        if code > NEG_INT_START && code < POS_INT_END {
-            let nbytes = if code >= INT_ZERO_CODE {
-                (code - INT_ZERO_CODE) as usize
-            } else {
-                (INT_ZERO_CODE - code) as usize
-            };
-
-            if inp.len() < nbytes {
-                return Err(UnpackError::OutOfData);
-            }
+fn unpack_uint(inp: &[u8]) -> Result<((bool, u128), &[u8]), UnpackError> {
+    let (negative, raw, inp) = unpack_raw(inp)?;
 
-            let mut buf = [0; 8];
-            buf[8 - nbytes..].copy_from_slice(&inp[..nbytes]);
-            let val = BigEndian::read_i64(&buf);
-
-            let out = if code >= INT_ZERO_CODE {
-                val
-            } else {
-                let max_value = 1i64
-                    .checked_shl(nbytes as u32 * 8)
-                    .unwrap_or(0)
-                    .wrapping_sub(1);
-                val.wrapping_sub(max_value)
-            };
-
-            Ok((out, &inp[nbytes..]))
-        } else {
-            Err(UnpackError::WrongCode)
+    let mut mag = 0u128;
+    for &b in raw {
+        if mag >> 120 != 0 {
+            return Err(UnpackError::OutOfRange);
         }
-    } else {
-        Err(UnpackError::OutOfData)
+        mag = mag << 8 | u128::from(if negative { !b } else { b });
     }
-}
-
-fn unpack_uint(inp: &[u8]) -> Result<(u64, &[u8]), UnpackError> {
-    if let Some((&code, inp)) = inp.split_first() {
-        if code >= INT_ZERO_CODE && code < POS_INT_END {
-            let nbytes = (code - INT_ZERO_CODE) as usize;
 
-            if inp.len() < nbytes {
-                return Err(UnpackError::OutOfData);
-            }
+    Ok(((negative, mag), inp))
+}
 
-            let mut buf = [0; 8];
-            buf[8 - nbytes..].copy_from_slice(&inp[..nbytes]);
-            let out = BigEndian::read_u64(&buf);
+fn unpack_int(inp: &[u8]) -> Result<(i128, &[u8]), UnpackError> {
+    let ((negative, mag), inp) = unpack_uint(inp)?;
 
-            Ok((out, &inp[nbytes..]))
-        } else {
-            Err(UnpackError::WrongCode)
+    let out = if negative {
+        if mag > 1 << 127 {
+            return Err(UnpackError::OutOfRange);
         }
+        (mag as i128).wrapping_neg()
     } else {
-        Err(UnpackError::OutOfData)
-    }
+        i128::try_from(mag)?
+    };
+
+    Ok((out, inp))
 }
 
 macro_rules! impl_i {
     ($ty:ty) => {
         impl Pack for $ty {
             fn pack(&self, out: &mut Vec<u8>, _nested: bool) {
-                pack_int(i64::from(*self), out)
+                pack_int(i128::from(*self), out)
             }
         }
 
-        impl Unpack for $ty {
-            fn unpack(inp: &[u8], _nested: bool) -> Result<(Self, &[u8]), UnpackError> {
+        impl<'de> Unpack<'de> for $ty {
+            fn unpack(inp: &'de [u8], _nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
                 let (out, inp) = unpack_int(inp)?;
                 let out = TryFrom::try_from(out)?;
                 Ok((out, inp))
@@ -123,20 +132,31 @@ macro_rules! impl_i {
     };
 }
 
+// Takes extra items for the Pack and Unpack impls, for u8
 macro_rules! impl_u {
     ($ty:ty) => {
+        impl_u!($ty, {}, {});
+    };
+    ($ty:ty, { $($pack:tt)* }, { $($unpack:tt)* }) => {
         impl Pack for $ty {
             fn pack(&self, out: &mut Vec<u8>, _nested: bool) {
-                pack_uint(u64::from(*self), out)
+                pack_uint(false, u128::from(*self), out)
             }
+
+            $($pack)*
         }
 
-        impl Unpack for $ty {
-            fn unpack(inp: &[u8], _nested: bool) -> Result<(Self, &[u8]), UnpackError> {
-                let (out, inp) = unpack_uint(inp)?;
+        impl<'de> Unpack<'de> for $ty {
+            fn unpack(inp: &'de [u8], _nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
+                let ((negative, out), inp) = unpack_uint(inp)?;
+                if negative && out != 0 {
+                    return Err(UnpackError::OutOfRange);
+                }
                 let out = TryFrom::try_from(out)?;
                 Ok((out, inp))
             }
+
+            $($unpack)*
         }
     };
 }
@@ -145,15 +165,31 @@ impl_i!(i8);
 impl_i!(i16);
 impl_i!(i32);
 impl_i!(i64);
+impl_i!(i128);
 
-impl_u!(u8);
+impl_u!(
+    u8,
+    {
+        fn pack_slice(items: &[u8], out: &mut Vec<u8>, _nested: bool) {
+            pack_byte_string(items, out)
+        }
+    },
+    {
+        fn unpack_vec(inp: &'de [u8], _nested: bool) -> Result<(Vec<u8>, &'de [u8]), UnpackError> {
+            let (bytes, inp) = unpack_byte_string(inp)?;
+            Ok((bytes.into_owned(), inp))
+        }
+    }
+);
 impl_u!(u16);
 impl_u!(u32);
 impl_u!(u64);
+impl_u!(u128);
 
 #[cfg(test)]
 mod test {
     use crate::tuple::test::{test_pack, test_pack_unpack};
+    use crate::tuple::{Unpack, UnpackError};
     use rand::random;
 
     #[test]
@@ -181,9 +217,37 @@ mod test {
         test_pack(123456789u64, &mut buf, &[0x18, 0x07, 0x5b, 0xcd, 0x15]);
         test_pack(i64::max_value() as u64, &mut buf, &[0x1c, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
         test_pack(u64::max_value() - 1, &mut buf, &[0x1c, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe]);
-        // Apple's fdb.tuple encodes this slightly less normalized, as [0x1d, 0x08, 0xff, ...]
-        // Why? I don't know. It'll still decode this more compact representation
-        test_pack(u64::max_value(), &mut buf, &[0x1c, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        // The official bindings switch to the 9+ byte encoding at 2^64-1 rather than 2^64
+        test_pack(u64::max_value(), &mut buf, &[0x1d, 0x08, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn test_pack_big_int() {
+        let mut buf = Vec::new();
+        test_pack(1i128 << 64, &mut buf, &[0x1d, 0x09, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        test_pack(-(1i128 << 64), &mut buf, &[0x0b, 0xf6, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        test_pack(-(1i128 << 64) + 1, &mut buf, &[0x0b, 0xf7, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        test_pack(-(1i128 << 64) + 2, &mut buf, &[0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01]);
+        test_pack(u128::max_value(), &mut buf, &[0x1d, 0x10, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        test_pack(i128::min_value(), &mut buf, &[0x0b, 0xef, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn test_unpack_int() {
+        // Every form the official bindings produce, as well as the shorter one we used to
+        let (val, _) = u64::unpack(&[0x1c, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff], false).unwrap();
+        assert_eq!(val, u64::max_value());
+        let (val, _) = u64::unpack(&[0x1d, 0x08, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff], false).unwrap();
+        assert_eq!(val, u64::max_value());
+        let (val, _) = i64::unpack(&[0x1d, 0x01, 0x05], false).unwrap();
+        assert_eq!(val, 5);
+        let (val, _) = i64::unpack(&[0x0b, 0xfe, 0xfa], false).unwrap();
+        assert_eq!(val, -5);
+
+        assert_eq!(u64::unpack(&[0x1d, 0x09, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], false), Err(UnpackError::OutOfRange));
+        assert_eq!(u64::unpack(&[0x13, 0xfe], false), Err(UnpackError::OutOfRange));
+        assert_eq!(i8::unpack(&[0x16, 0x01, 0x00], false), Err(UnpackError::OutOfRange));
+        assert_eq!(i64::unpack(&[0x1d, 0x09, 0x01], false), Err(UnpackError::OutOfData));
     }
 
     #[test]
@@ -202,6 +266,19 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_pack_unpack_int128() {
+        let mut buf = Vec::new();
+        test_pack_unpack(i128::min_value(), &mut buf);
+        test_pack_unpack(i128::max_value(), &mut buf);
+        test_pack_unpack(u128::max_value(), &mut buf);
+        test_pack_unpack(i128::from(i64::min_value()) - 1, &mut buf);
+        test_pack_unpack(u128::from(u64::max_value()) + 1, &mut buf);
+        for _ in 0..100000 {
+            test_pack_unpack(random::<i128>() >> (random::<u32>() % 128), &mut buf);
+        }
+    }
+
     #[test]
     fn test_pack_unpack_uint() {
         let mut buf = Vec::new();
@@ -6,23 +6,46 @@ impl<T> Pack for Option<T>
 where
     T: Pack,
 {
-    fn pack(&self, out: &mut Vec<u8>, _nested: bool) {
+    fn pack(&self, out: &mut Vec<u8>, nested: bool) {
         if let Some(v) = self {
-            T::pack(v, out, false)
+            T::pack(v, out, nested)
         } else {
             out.push(NULL_CODE);
         }
     }
+
+    // Within nested tuples, nulls are escaped so they can't be mistaken for the end of the tuple
+    fn pack_element(&self, out: &mut Vec<u8>, in_nested: bool) {
+        if let Some(v) = self {
+            T::pack_element(v, out, in_nested)
+        } else if in_nested {
+            out.extend_from_slice(&[NULL_CODE, 0xff]);
+        } else {
+            out.push(NULL_CODE);
+        }
+    }
+
+    fn pack_flat(&self, out: &mut Vec<u8>, in_nested: bool) {
+        if let Some(v) = self {
+            T::pack_flat(v, out, in_nested)
+        } else {
+            self.pack_element(out, in_nested)
+        }
+    }
 }
 
-impl<T> Unpack for Option<T>
+impl<'de, T> Unpack<'de> for Option<T>
 where
-    T: Unpack,
+    T: Unpack<'de>,
 {
-    fn unpack(inp: &[u8], nested: bool) -> Result<(Self, &[u8]), UnpackError> {
+    fn unpack(inp: &'de [u8], nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
         if let Some((&code, inp_some)) = inp.split_first() {
             if code == NULL_CODE {
-                Ok((None, inp_some))
+                // The escape only follows nulls in nested tuples, but can't be anything else
+                match inp_some.split_first() {
+                    Some((&0xff, inp_escaped)) if nested => Ok((None, inp_escaped)),
+                    _ => Ok((None, inp_some)),
+                }
             } else {
                 match T::unpack(inp, nested) {
                     Ok((v, inp)) => Ok((Some(v), inp)),
@@ -33,11 +56,21 @@ where
             Err(UnpackError::OutOfData)
         }
     }
+
+    fn unpack_flat(inp: &'de [u8], in_nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
+        match inp.first() {
+            Some(&NULL_CODE) | None => Self::unpack(inp, in_nested),
+            Some(_) => match T::unpack_flat(inp, in_nested) {
+                Ok((v, inp)) => Ok((Some(v), inp)),
+                Err(err) => Err(err),
+            },
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::tuple::test::test_pack_unpack;
+    use crate::tuple::test::{test_pack, test_pack_unpack};
 
     #[test]
     fn test_pack_unpack_option() {
@@ -45,5 +78,23 @@ mod test {
         test_pack_unpack(Some(32), &mut buf);
         let none_val: Option<i32> = None;
         test_pack_unpack(none_val, &mut buf);
+        test_pack_unpack((none_val, (none_val, 1), vec![none_val, Some(2)]), &mut buf);
+        test_pack_unpack(Some((1, 2)), &mut buf);
+    }
+
+    #[test]
+    fn test_pack_option_nested() {
+        let mut buf = Vec::new();
+        let none_val: Option<i32> = None;
+        test_pack(none_val, &mut buf, b"\x00");
+        test_pack((none_val, 1), &mut buf, b"\x00\x15\x01");
+        test_pack(((none_val,),), &mut buf, b"\x05\x00\xff\x00");
+        test_pack(
+            (vec![none_val, Some(1)],),
+            &mut buf,
+            b"\x05\x00\xff\x15\x01\x00",
+        );
+        test_pack((Some((1, 2)),), &mut buf, b"\x05\x15\x01\x15\x02\x00");
+        test_pack((&none_val, (&none_val,)), &mut buf, b"\x00\x05\x00\xff\x00");
     }
 }
@@ -0,0 +1,556 @@
+// A readable notation for packed tuples, for logs and command line tools, f.e.
+//
+//   ("users", 42, b"\x00\xff", nested(1, 2))
+//
+// Strings and byte strings are quoted, escaping " and \ with a backslash and other unprintable
+// bytes as \xNN. Integers, doubles (which always have a decimal point or an exponent), true,
+// false and null are written as is, and everything else as a call: nested(...), float(1.5),
+// uuid(...) and versionstamp(<transaction version in hex>, <user version>). Keys that aren't
+// tuples, or hold a NaN other than the standard one, are written the way fdbcli writes them,
+// with a leading ( escaped so they can't be mistaken for a tuple.
+
+use crate::tuple::{Element, Pack, Unpack, Versionstamp};
+use foundationdb::Printable as RawPrintable;
+#[cfg(feature = "num-bigint")]
+use num_bigint::BigInt;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::{f32, f64};
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
+
+/*
+ * Formatting
+ */
+
+// Formats a key as a tuple if it is one, and as escaped bytes otherwise
+pub struct Printable<'a>(pub &'a [u8]);
+
+impl<'a> Display for Printable<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        if let Some(elements) = as_elements(self.0) {
+            write_elements(f, &elements)
+        } else if let Some((b'(', rest)) = self.0.split_first() {
+            write!(f, "\\x28{}", RawPrintable(rest))
+        } else {
+            write!(f, "{}", RawPrintable(self.0))
+        }
+    }
+}
+
+// The key's elements, if it's a tuple that packs back to exactly the same bytes and prints
+// exactly, so that parsing the output always gives back the key
+fn as_elements(key: &[u8]) -> Option<Vec<Element>> {
+    let (elements, _) = Vec::<Element>::unpack(key, false).ok()?;
+    let mut packed = Vec::new();
+    elements.pack(&mut packed, false);
+    if packed == key && elements.iter().all(prints_exactly) {
+        Some(elements)
+    } else {
+        None
+    }
+}
+
+// Every NaN prints as NaN, which parses as the standard one, so any other NaN is lost
+fn prints_exactly(element: &Element) -> bool {
+    match element {
+        Element::Float(v) if v.is_nan() => v.to_bits() == f32::NAN.to_bits(),
+        Element::Double(v) if v.is_nan() => v.to_bits() == f64::NAN.to_bits(),
+        Element::Nested(v) => v.iter().all(prints_exactly),
+        _ => true,
+    }
+}
+
+impl Display for Element {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Element::Null => write!(f, "null"),
+            Element::Bytes(v) => {
+                write!(f, "b\"")?;
+                for &b in v {
+                    write_byte(f, b)?;
+                }
+                write!(f, "\"")
+            }
+            Element::String(v) => {
+                write!(f, "\"")?;
+                for c in v.chars() {
+                    if c.is_ascii() {
+                        write_byte(f, c as u8)?;
+                    } else {
+                        write!(f, "{}", c)?;
+                    }
+                }
+                write!(f, "\"")
+            }
+            Element::Int(v) => write!(f, "{}", v),
+            #[cfg(feature = "num-bigint")]
+            Element::BigInt(v) => write!(f, "{}", v),
+            Element::Float(v) => write!(f, "float({:?})", v),
+            Element::Double(v) => write!(f, "{:?}", v),
+            Element::Bool(v) => write!(f, "{}", v),
+            #[cfg(feature = "uuid")]
+            Element::Uuid(v) => write!(f, "uuid({})", v),
+            Element::Versionstamp(v) => {
+                write!(f, "versionstamp(")?;
+                for b in v.transaction_version() {
+                    write!(f, "{:02x}", b)?;
+                }
+                write!(f, ", {})", v.user_version())
+            }
+            Element::Nested(v) => {
+                write!(f, "nested")?;
+                write_elements(f, v)
+            }
+        }
+    }
+}
+
+fn write_elements(f: &mut Formatter, elements: &[Element]) -> Result<(), fmt::Error> {
+    write!(f, "(")?;
+    for (i, element) in elements.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", element)?;
+    }
+    write!(f, ")")
+}
+
+// A byte within quotes
+fn write_byte(f: &mut Formatter, b: u8) -> Result<(), fmt::Error> {
+    match b {
+        b'"' => write!(f, "\\\""),
+        b'\\' => write!(f, "\\\\"),
+        32..=126 => write!(f, "{}", b as char),
+        _ => write!(f, "\\x{:02x}", b),
+    }
+}
+
+/*
+ * Parsing
+ */
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    offset: usize,
+    reason: &'static str,
+}
+
+impl ParseError {
+    // Byte offset into the input at which parsing failed
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{} at offset {}", self.reason, self.offset)
+    }
+}
+
+impl error::Error for ParseError {}
+
+// Parses the output of Printable back into the key
+pub fn parse_printable(inp: &str) -> Result<Vec<u8>, ParseError> {
+    let mut parser = Parser {
+        inp: inp.as_bytes(),
+        pos: 0,
+    };
+
+    if parser.peek() != Some(b'(') {
+        return parser.raw();
+    }
+
+    let elements = parser.elements()?;
+    parser.skip_whitespace();
+    if parser.peek().is_some() {
+        return Err(parser.error("expected the end of the key"));
+    }
+
+    let mut out = Vec::new();
+    elements.pack(&mut out, false);
+    Ok(out)
+}
+
+struct Parser<'a> {
+    inp: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, reason: &'static str) -> ParseError {
+        ParseError {
+            offset: self.pos,
+            reason,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.inp.get(self.pos).cloned()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map_or(false, |b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: u8, reason: &'static str) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(reason))
+        }
+    }
+
+    // A run of characters that make up a number, keyword, uuid or hex string
+    fn token(&mut self) -> &'a str {
+        let start = self.pos;
+        while self
+            .peek()
+            .map_or(false, |b| b.is_ascii_alphanumeric() || b"+-._".contains(&b))
+        {
+            self.pos += 1;
+        }
+        // Only ASCII was consumed, so this is on character boundaries
+        std::str::from_utf8(&self.inp[start..self.pos]).unwrap()
+    }
+
+    // A parenthesized, comma separated list of elements, allowing a trailing comma
+    fn elements(&mut self) -> Result<Vec<Element>, ParseError> {
+        self.expect(b'(', "expected (")?;
+        let mut out = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(b')') {
+                self.pos += 1;
+                return Ok(out);
+            }
+
+            out.push(self.element()?);
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b')') => {}
+                _ => return Err(self.error("expected , or )")),
+            }
+        }
+    }
+
+    fn element(&mut self) -> Result<Element, ParseError> {
+        self.skip_whitespace();
+        match (self.peek(), self.inp.get(self.pos + 1)) {
+            (Some(b'"'), _) => {
+                let start = self.pos;
+                let bytes = self.quoted()?;
+                String::from_utf8(bytes)
+                    .map(Element::String)
+                    .map_err(|_| ParseError {
+                        offset: start,
+                        reason: "string is not valid UTF-8",
+                    })
+            }
+            (Some(b'b'), Some(b'"')) => {
+                self.pos += 1;
+                self.quoted().map(Element::Bytes)
+            }
+            _ => {
+                let start = self.pos;
+                let token = self.token();
+                self.skip_whitespace();
+                if self.peek() == Some(b'(') {
+                    return self.call(token, start);
+                }
+                match token {
+                    "" => Err(self.error("expected an element")),
+                    "null" => Ok(Element::Null),
+                    "true" => Ok(Element::Bool(true)),
+                    "false" => Ok(Element::Bool(false)),
+                    _ => number(token, start),
+                }
+            }
+        }
+    }
+
+    fn call(&mut self, name: &str, start: usize) -> Result<Element, ParseError> {
+        if name == "nested" {
+            return self.elements().map(Element::Nested);
+        }
+
+        self.expect(b'(', "expected (")?;
+        self.skip_whitespace();
+        let arg_start = self.pos;
+        let arg = self.token();
+        let bad_arg = |reason| ParseError {
+            offset: arg_start,
+            reason,
+        };
+
+        let element = match name {
+            "float" => arg
+                .parse()
+                .map(Element::Float)
+                .map_err(|_| bad_arg("expected a float"))?,
+            #[cfg(feature = "uuid")]
+            "uuid" => Uuid::parse_str(arg)
+                .map(Element::Uuid)
+                .map_err(|_| bad_arg("expected a uuid"))?,
+            "versionstamp" => {
+                let transaction_version =
+                    parse_hex(arg).ok_or_else(|| bad_arg("expected 10 bytes of hex"))?;
+                self.expect(b',', "expected ,")?;
+                self.skip_whitespace();
+                let user_start = self.pos;
+                let user_version = self.token().parse().map_err(|_| ParseError {
+                    offset: user_start,
+                    reason: "expected a user version",
+                })?;
+                Element::Versionstamp(Versionstamp::complete(transaction_version, user_version))
+            }
+            _ => {
+                return Err(ParseError {
+                    offset: start,
+                    reason: "unknown element",
+                })
+            }
+        };
+
+        self.expect(b')', "expected )")?;
+        Ok(element)
+    }
+
+    // The contents of a quoted string, without the quotes and with escapes undone
+    fn quoted(&mut self) -> Result<Vec<u8>, ParseError> {
+        self.expect(b'"', "expected \"")?;
+        let mut out = Vec::new();
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some(b'\\') => out.push(self.escape()?),
+                Some(b) => {
+                    out.push(b);
+                    self.pos += 1;
+                }
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    // \\, \" or \xNN
+    fn escape(&mut self) -> Result<u8, ParseError> {
+        let start = self.pos;
+        let b = match self.inp.get(self.pos + 1) {
+            Some(&b) if b == b'\\' || b == b'"' => {
+                self.pos += 2;
+                b
+            }
+            Some(b'x') => {
+                let hex = self.inp.get(self.pos + 2..self.pos + 4);
+                let b = hex.and_then(|hex| std::str::from_utf8(hex).ok());
+                let b = b.and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                self.pos += 4;
+                b.ok_or(ParseError {
+                    offset: start,
+                    reason: "bad \\x escape",
+                })?
+            }
+            _ => {
+                return Err(ParseError {
+                    offset: start,
+                    reason: "unknown escape",
+                })
+            }
+        };
+        Ok(b)
+    }
+
+    // Bytes as fdbcli prints them, with \\ and \xNN escapes
+    fn raw(&mut self) -> Result<Vec<u8>, ParseError> {
+        let mut out = Vec::new();
+        while let Some(b) = self.peek() {
+            if b == b'\\' {
+                out.push(self.escape()?);
+            } else {
+                out.push(b);
+                self.pos += 1;
+            }
+        }
+        Ok(out)
+    }
+}
+
+// Integers are plain digits, anything else that parses as an f64 is a double
+fn number(token: &str, start: usize) -> Result<Element, ParseError> {
+    let digits = if token.starts_with('-') {
+        &token[1..]
+    } else {
+        token
+    };
+    let error = |reason| ParseError {
+        offset: start,
+        reason,
+    };
+
+    if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+        match token.parse() {
+            Ok(v) => Ok(Element::Int(v)),
+            #[cfg(feature = "num-bigint")]
+            Err(_) => Ok(Element::BigInt(token.parse::<BigInt>().unwrap())),
+            #[cfg(not(feature = "num-bigint"))]
+            Err(_) => Err(error("integer out of range")),
+        }
+    } else {
+        token
+            .parse()
+            .map(Element::Double)
+            .map_err(|_| error("expected an element"))
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<[u8; 10]> {
+    let mut out = [0; 10];
+    if hex.len() != out.len() * 2 {
+        return None;
+    }
+    for (i, b) in out.iter_mut().enumerate() {
+        *b = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_printable, Printable};
+    use crate::tuple::{Element, Pack, Versionstamp};
+
+    fn packed<T: Pack>(value: T) -> Vec<u8> {
+        let mut out = Vec::new();
+        value.pack(&mut out, false);
+        out
+    }
+
+    #[test]
+    fn test_printable() {
+        let key = packed(("users", 42, &b"\x00\xff"[..], (1, 2)));
+        assert_eq!(
+            Printable(&key).to_string(),
+            r#"("users", 42, b"\x00\xff", nested(1, 2))"#
+        );
+        assert_eq!(Printable(b"").to_string(), "()");
+        assert_eq!(
+            Printable(&packed((None::<i32>, true, -1.5, 2f32, ((),)))).to_string(),
+            "(null, true, -1.5, float(2.0), nested(nested()))"
+        );
+        assert_eq!(
+            Printable(&packed(("a\"\\\u{0}\u{d4}", &b"\"\x7f"[..]))).to_string(),
+            r#"("a\"\\\x00Ô", b"\"\x7f")"#
+        );
+        assert_eq!(
+            Printable(&packed((Versionstamp::incomplete(3),))).to_string(),
+            "(versionstamp(ffffffffffffffffffff, 3))"
+        );
+    }
+
+    #[test]
+    fn test_printable_raw() {
+        // Not tuples
+        assert_eq!(Printable(b"\xffhello\\").to_string(), "\\xffhello\\\\");
+        assert_eq!(Printable(b"(abc").to_string(), "\\x28abc");
+        // Tuples, but not as these bindings would pack them
+        assert_eq!(Printable(b"\x15\x00").to_string(), "\\x15\\x00");
+        // Tuples holding a NaN that would print like the standard one
+        let nan = f64::from_bits(0xfff8_0000_0000_0001);
+        assert_eq!(
+            Printable(&packed((1, nan))).to_string(),
+            "\\x15\\x01!\\x00\\x07\\xff\\xff\\xff\\xff\\xff\\xfe"
+        );
+        assert_eq!(
+            Printable(&packed((std::f64::NAN, std::f32::NAN))).to_string(),
+            "(NaN, float(NaN))"
+        );
+    }
+
+    #[test]
+    fn test_parse_printable() {
+        assert_eq!(
+            parse_printable(r#"("users", 42, b"\x00\xff", nested(1, 2))"#),
+            Ok(packed(("users", 42, &b"\x00\xff"[..], (1, 2))))
+        );
+        assert_eq!(
+            parse_printable("( null ,-7, 1e3,float(-0.5),nested( ),) "),
+            Ok(packed((None::<i32>, -7, 1000f64, -0.5f32, ())))
+        );
+        assert_eq!(parse_printable("\\x28abc\\\\"), Ok(b"(abc\\".to_vec()));
+        assert_eq!(parse_printable("hello"), Ok(b"hello".to_vec()));
+    }
+
+    #[test]
+    fn test_printable_round_trip() {
+        let keys = vec![
+            packed(("F\u{d4}O\u{0}bar", &b"foo\x00bar"[..], i64::min_value())),
+            packed((vec![None::<i32>, Some(1)], (None::<i32>,))),
+            packed((std::f64::INFINITY, std::f64::NEG_INFINITY, -0f64, 0.1f32)),
+            packed((Versionstamp::complete([1; 10], 65535), false)),
+            packed((std::f64::NAN, std::f32::NAN)),
+            packed((f64::from_bits(0xfff8_0000_0000_0001),)),
+            packed(((1, f32::from_bits(0x7fc0_0001)),)),
+            packed(vec![Element::Nested(vec![Element::Nested(vec![])])]),
+            b"\x02unterminated".to_vec(),
+            b"(\\\xff\x00 ".to_vec(),
+            Vec::new(),
+        ];
+        for key in keys {
+            let printed = Printable(&key).to_string();
+            assert_eq!(parse_printable(&printed), Ok(key), "parsing {}", printed);
+        }
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn test_printable_uuid() {
+        use uuid::Uuid;
+
+        let uuid = Uuid::parse_str("00112233-4455-6677-8899-aabbccddeeff").unwrap();
+        let printed = "(uuid(00112233-4455-6677-8899-aabbccddeeff))";
+        assert_eq!(Printable(&packed((uuid,))).to_string(), printed);
+        assert_eq!(parse_printable(printed), Ok(packed((uuid,))));
+    }
+
+    #[cfg(feature = "num-bigint")]
+    #[test]
+    fn test_printable_bigint() {
+        let key = packed((u64::max_value(), -(1i128 << 70)));
+        let printed = "(18446744073709551615, -1180591620717411303424)";
+        assert_eq!(Printable(&key).to_string(), printed);
+        assert_eq!(parse_printable(printed), Ok(key));
+    }
+
+    #[test]
+    fn test_parse_printable_invalid() {
+        let offset = |inp| parse_printable(inp).unwrap_err().offset();
+        assert_eq!(offset("(1"), 2);
+        assert_eq!(offset("(1 2)"), 3);
+        assert_eq!(offset("(\"abc)"), 6);
+        assert_eq!(offset("(b\"\\q\")"), 3);
+        assert_eq!(offset("(b\"\\x4\")"), 3);
+        assert_eq!(offset("(\"\\xff\")"), 1);
+        assert_eq!(offset("(foo)"), 1);
+        assert_eq!(offset("(frob(1))"), 1);
+        assert_eq!(offset("(float(x))"), 7);
+        assert_eq!(offset("(versionstamp(ff, 1))"), 14);
+        assert_eq!(offset("(1) x"), 4);
+        assert_eq!(offset("\\q"), 0);
+        assert_eq!(
+            parse_printable("(,)").unwrap_err().to_string(),
+            "expected an element at offset 1"
+        );
+    }
+}
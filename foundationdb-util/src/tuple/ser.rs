@@ -0,0 +1,502 @@
+// serde support: any Serialize type packs to the same bytes, and so sorts the same way, as the
+// Pack impls would produce for it. Structs, tuples, sequences and maps become tuples (flat at
+// the root, nested within another tuple), None becomes null, and enum variants become their
+// name, followed by their fields if they have any, matching #[derive(Pack)].
+//
+// Note that serde sees Vec<u8> as a sequence of integers, not as bytes. Use serde_bytes or
+// similar to get the byte string encoding.
+
+use crate::tuple::{Pack, UnpackError};
+use serde::ser::{self, Serialize};
+use std::error;
+use std::fmt::{self, Display, Formatter};
+
+const NULL_CODE: u8 = 0x00;
+const NESTED_CODE: u8 = 0x05;
+
+pub fn to_vec<T>(value: &T) -> Result<Vec<u8>, SerdeError>
+where
+    T: Serialize + ?Sized,
+{
+    let mut out = Vec::new();
+    value.serialize(Serializer::new(&mut out))?;
+    Ok(out)
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SerdeError {
+    Message(String),
+    Unpack(UnpackError),
+}
+
+impl Display for SerdeError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            SerdeError::Message(msg) => write!(f, "{}", msg),
+            SerdeError::Unpack(err) => write!(f, "bad tuple encoding: {:?}", err),
+        }
+    }
+}
+
+impl error::Error for SerdeError {}
+
+impl ser::Error for SerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerdeError::Message(msg.to_string())
+    }
+}
+
+impl From<UnpackError> for SerdeError {
+    fn from(err: UnpackError) -> Self {
+        SerdeError::Unpack(err)
+    }
+}
+
+/*
+ * Serializer
+ */
+
+pub struct Serializer<'a> {
+    out: &'a mut Vec<u8>,
+    nested: bool,
+    // Whether the value is an element of a nested tuple, where nulls are escaped
+    in_nested: bool,
+}
+
+impl<'a> Serializer<'a> {
+    // Appends the value to out as the root of a tuple
+    pub fn new(out: &'a mut Vec<u8>) -> Self {
+        Serializer {
+            out,
+            nested: false,
+            in_nested: false,
+        }
+    }
+
+    fn pack<T: Pack + ?Sized>(self, v: &T) -> Result<(), SerdeError> {
+        v.pack(self.out, self.nested);
+        Ok(())
+    }
+
+    fn begin(self) -> Compound<'a> {
+        if self.nested {
+            self.out.push(NESTED_CODE);
+        }
+        Compound {
+            out: self.out,
+            nested: self.nested,
+        }
+    }
+}
+
+impl<'a> ser::Serializer for Serializer<'a> {
+    type Ok = ();
+    type Error = SerdeError;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), SerdeError> {
+        self.pack(&v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), SerdeError> {
+        self.pack(&v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), SerdeError> {
+        self.pack(&v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), SerdeError> {
+        self.pack(&v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), SerdeError> {
+        self.pack(&v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), SerdeError> {
+        self.pack(&v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), SerdeError> {
+        self.pack(&v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), SerdeError> {
+        self.pack(&v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), SerdeError> {
+        self.pack(&v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), SerdeError> {
+        self.pack(&v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), SerdeError> {
+        self.pack(&v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), SerdeError> {
+        self.pack(&v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), SerdeError> {
+        self.pack(&v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), SerdeError> {
+        self.pack(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), SerdeError> {
+        self.pack(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), SerdeError> {
+        self.pack(v)
+    }
+
+    fn serialize_none(self) -> Result<(), SerdeError> {
+        if self.in_nested {
+            self.out.extend_from_slice(&[NULL_CODE, 0xff]);
+        } else {
+            self.out.push(NULL_CODE);
+        }
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), SerdeError> {
+        self.pack(&())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), SerdeError> {
+        self.pack(&())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), SerdeError> {
+        self.pack(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        let mut compound = self.begin();
+        compound.element(variant)?;
+        compound.element(value)?;
+        compound.end()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a>, SerdeError> {
+        Ok(self.begin())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Compound<'a>, SerdeError> {
+        Ok(self.begin())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, SerdeError> {
+        Ok(self.begin())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, SerdeError> {
+        let mut compound = self.begin();
+        compound.element(variant)?;
+        Ok(compound)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a>, SerdeError> {
+        Ok(self.begin())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, SerdeError> {
+        Ok(self.begin())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, SerdeError> {
+        let mut compound = self.begin();
+        compound.element(variant)?;
+        Ok(compound)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/*
+ * Compound
+ */
+
+// The elements of a tuple being serialized. Maps alternate keys and values.
+pub struct Compound<'a> {
+    out: &'a mut Vec<u8>,
+    nested: bool,
+}
+
+impl<'a> Compound<'a> {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        value.serialize(Serializer {
+            out: &mut *self.out,
+            nested: true,
+            in_nested: self.nested,
+        })
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        if self.nested {
+            self.out.push(0x00);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeSeq for Compound<'a> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        Compound::end(self)
+    }
+}
+
+impl<'a> ser::SerializeTuple for Compound<'a> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        Compound::end(self)
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for Compound<'a> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        Compound::end(self)
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for Compound<'a> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        Compound::end(self)
+    }
+}
+
+impl<'a> ser::SerializeMap for Compound<'a> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        self.element(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        Compound::end(self)
+    }
+}
+
+impl<'a> ser::SerializeStruct for Compound<'a> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        Compound::end(self)
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for Compound<'a> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        Compound::end(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::to_vec;
+    use crate::tuple::Pack;
+    use serde_derive::Serialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct UserKey<'a> {
+        name: &'a str,
+        id: i64,
+        deleted: Option<bool>,
+    }
+
+    #[derive(Serialize)]
+    struct Id(u32);
+
+    #[derive(Serialize)]
+    enum Event {
+        Created,
+        Moved(i64),
+        Renamed { from: String },
+    }
+
+    fn packed<T: Pack>(value: T) -> Vec<u8> {
+        let mut out = Vec::new();
+        value.pack(&mut out, false);
+        out
+    }
+
+    #[test]
+    fn test_to_vec() {
+        let key = UserKey {
+            name: "a",
+            id: 1,
+            deleted: None,
+        };
+        assert_eq!(to_vec(&key).unwrap(), packed(("a", 1, None::<bool>)));
+        assert_eq!(
+            to_vec(&(1, &key)).unwrap(),
+            packed((1, ("a", 1, None::<bool>)))
+        );
+        assert_eq!(
+            to_vec(&(None::<bool>, (None::<bool>,))).unwrap(),
+            b"\x00\x05\x00\xff\x00"
+        );
+        assert_eq!(to_vec(&Id(7)).unwrap(), packed(7));
+        assert_eq!(
+            to_vec(&(1.5f32, -2i8, 'x')).unwrap(),
+            packed((1.5f32, -2, "x"))
+        );
+        assert_eq!(
+            to_vec(&i128::min_value()).unwrap(),
+            packed(i128::min_value())
+        );
+        assert_eq!(to_vec(&vec![1, 2]).unwrap(), packed((1, 2)));
+        assert_eq!(to_vec(&((), ((),))).unwrap(), b"\x05\x00\x05\x05\x00\x00");
+
+        let mut map = BTreeMap::new();
+        map.insert("a", 1);
+        map.insert("b", 2);
+        assert_eq!(to_vec(&map).unwrap(), packed(("a", 1, "b", 2)));
+    }
+
+    #[test]
+    fn test_to_vec_enum() {
+        assert_eq!(to_vec(&Event::Created).unwrap(), packed("Created"));
+        assert_eq!(to_vec(&Event::Moved(1)).unwrap(), packed(("Moved", 1)));
+        assert_eq!(
+            to_vec(&(Event::Moved(1), Event::Created)).unwrap(),
+            packed((("Moved", 1), "Created"))
+        );
+        assert_eq!(
+            to_vec(&(Event::Renamed {
+                from: "a".to_string()
+            },))
+            .unwrap(),
+            packed((("Renamed", "a"),))
+        );
+    }
+
+    #[test]
+    fn test_to_vec_order() {
+        // In order
+        let values = [
+            (-300i64, "b"),
+            (-1, "a"),
+            (0, ""),
+            (0, "a"),
+            (1, "\u{0}"),
+            (70000, "z"),
+        ];
+        let keys: Vec<Vec<u8>> = values.iter().map(|v| to_vec(v).unwrap()).collect();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+    }
+}
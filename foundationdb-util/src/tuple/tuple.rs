@@ -11,22 +11,28 @@ macro_rules! impl_tuple {
             $($ty: Pack,)*
         {
             fn pack(&self, out: &mut Vec<u8>, nested: bool) {
-                let ( $($id,)* ) = self;
                 if nested {
                     out.push(NESTED_CODE);
                 }
-                $($ty::pack($id, out, true);)*
+                self.pack_flat(out, nested);
                 if nested {
                     out.push(0x00);
                 }
             }
+
+            // () doesn't use out or in_nested
+            #[allow(unused_variables)]
+            fn pack_flat(&self, out: &mut Vec<u8>, in_nested: bool) {
+                let ( $($id,)* ) = self;
+                $($ty::pack_element($id, out, in_nested);)*
+            }
         }
 
-        impl< $($ty,)* > Unpack for ( $($ty,)* )
+        impl<'de, $($ty,)* > Unpack<'de> for ( $($ty,)* )
         where
-            $($ty: Unpack,)*
+            $($ty: Unpack<'de>,)*
         {
-            fn unpack(inp: &[u8], nested: bool) -> Result<(Self, &[u8]), UnpackError> {
+            fn unpack(inp: &'de [u8], nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
                 let inp = if nested {
                     expect(inp, NESTED_CODE)?
                 } else {
@@ -40,6 +46,13 @@ macro_rules! impl_tuple {
                 };
                 Ok((( $($id,)* ), inp))
             }
+
+            fn unpack_flat(
+                inp: &'de [u8],
+                _in_nested: bool,
+            ) -> Result<(Self, &'de [u8]), UnpackError> {
+                Self::unpack(inp, false)
+            }
         }
 
         impl< $($ty,)* > Tuple for ( $($ty,)* )
@@ -56,10 +69,134 @@ impl_tuple!(v1: T1, v2: T2, v3: T3, v4: T4, v5: T5);
 impl_tuple!(v1: T1, v2: T2, v3: T3, v4: T4, v5: T5, v6: T6);
 impl_tuple!(v1: T1, v2: T2, v3: T3, v4: T4, v5: T5, v6: T6, v7: T7);
 impl_tuple!(v1: T1, v2: T2, v3: T3, v4: T4, v5: T5, v6: T6, v7: T7, v8: T8);
+impl_tuple!(v1: T1, v2: T2, v3: T3, v4: T4, v5: T5, v6: T6, v7: T7, v8: T8, v9: T9);
+impl_tuple!(v1: T1, v2: T2, v3: T3, v4: T4, v5: T5, v6: T6, v7: T7, v8: T8, v9: T9, v10: T10);
+impl_tuple!(
+    v1: T1, v2: T2, v3: T3, v4: T4, v5: T5, v6: T6, v7: T7, v8: T8, v9: T9, v10: T10, v11: T11
+);
+impl_tuple!(
+    v1: T1, v2: T2, v3: T3, v4: T4, v5: T5, v6: T6, v7: T7, v8: T8, v9: T9, v10: T10, v11: T11,
+    v12: T12
+);
+impl_tuple!(
+    v1: T1, v2: T2, v3: T3, v4: T4, v5: T5, v6: T6, v7: T7, v8: T8, v9: T9, v10: T10, v11: T11,
+    v12: T12, v13: T13
+);
+impl_tuple!(
+    v1: T1, v2: T2, v3: T3, v4: T4, v5: T5, v6: T6, v7: T7, v8: T8, v9: T9, v10: T10, v11: T11,
+    v12: T12, v13: T13, v14: T14
+);
+impl_tuple!(
+    v1: T1, v2: T2, v3: T3, v4: T4, v5: T5, v6: T6, v7: T7, v8: T8, v9: T9, v10: T10, v11: T11,
+    v12: T12, v13: T13, v14: T14, v15: T15
+);
+impl_tuple!(
+    v1: T1, v2: T2, v3: T3, v4: T4, v5: T5, v6: T6, v7: T7, v8: T8, v9: T9, v10: T10, v11: T11,
+    v12: T12, v13: T13, v14: T14, v15: T15, v16: T16
+);
+
+/*
+ * Sequences
+ */
+
+// Slices, vectors and arrays are tuples of any length, except for those of u8, which are byte
+// strings. The Pack::pack_slice and Unpack::unpack_vec hooks make that choice per item type.
+
+pub(crate) fn pack_items<T: Pack>(items: &[T], out: &mut Vec<u8>, nested: bool) {
+    if nested {
+        out.push(NESTED_CODE);
+    }
+    for item in items {
+        item.pack_element(out, nested);
+    }
+    if nested {
+        out.push(0x00);
+    }
+}
+
+pub(crate) fn unpack_items<'de, T: Unpack<'de>>(
+    inp: &'de [u8],
+    nested: bool,
+) -> Result<(Vec<T>, &'de [u8]), UnpackError> {
+    let mut inp = if nested {
+        expect(inp, NESTED_CODE)?
+    } else {
+        inp
+    };
+
+    let mut out = Vec::new();
+    loop {
+        match inp.first() {
+            // Within nested tuples, nulls are escaped and a bare null ends the tuple
+            Some(0x00) if nested && inp.get(1) != Some(&0xff) => return Ok((out, &inp[1..])),
+            None if nested => return Err(UnpackError::OutOfData),
+            None => return Ok((out, inp)),
+            Some(_) => {
+                let (item, rest) = T::unpack(inp, true)?;
+                out.push(item);
+                inp = rest;
+            }
+        }
+    }
+}
+
+impl<T: Pack> Pack for [T] {
+    fn pack(&self, out: &mut Vec<u8>, nested: bool) {
+        T::pack_slice(self, out, nested)
+    }
+}
+
+impl<T: Pack> Pack for Vec<T> {
+    fn pack(&self, out: &mut Vec<u8>, nested: bool) {
+        T::pack_slice(self, out, nested)
+    }
+}
+
+impl<'de, T: Unpack<'de>> Unpack<'de> for Vec<T> {
+    fn unpack(inp: &'de [u8], nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
+        T::unpack_vec(inp, nested)
+    }
+}
+
+impl<T> Tuple for Vec<T> {}
+
+macro_rules! impl_array {
+    ( $($n:expr),* ) => {
+        $(
+            impl<T: Pack> Pack for [T; $n] {
+                fn pack(&self, out: &mut Vec<u8>, nested: bool) {
+                    T::pack_slice(self, out, nested)
+                }
+            }
+
+            impl<'de, T: Unpack<'de> + Default> Unpack<'de> for [T; $n] {
+                fn unpack(inp: &'de [u8], nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
+                    let (items, inp) = T::unpack_vec(inp, nested)?;
+                    if items.len() != $n {
+                        return Err(UnpackError::OutOfRange);
+                    }
+                    let mut out = Self::default();
+                    for (slot, item) in out.iter_mut().zip(items) {
+                        *slot = item;
+                    }
+                    Ok((out, inp))
+                }
+            }
+
+            impl<T> Tuple for [T; $n] {}
+        )*
+    };
+}
+
+impl_array!(
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
+    26, 27, 28, 29, 30, 31, 32
+);
 
 #[cfg(test)]
 mod test {
-    use crate::tuple::test::test_pack_unpack;
+    use crate::tuple::test::{test_pack, test_pack_unpack};
+    use crate::tuple::{Pack, Unpack, UnpackError};
 
     #[test]
     fn test_pack_unpack_tuple() {
@@ -67,4 +204,52 @@ mod test {
         test_pack_unpack((42, true), &mut buf);
         test_pack_unpack((1, (2, 3)), &mut buf);
     }
+
+    #[test]
+    fn test_pack_unpack_tuple16() {
+        let tuple = (1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, "sixteen".to_string());
+        let mut buf = Vec::new();
+        tuple.pack(&mut buf, true);
+        assert_eq!(&buf[..3], b"\x05\x15\x01");
+        assert_eq!(&buf[buf.len() - 10..], b"\x02sixteen\x00\x00");
+
+        type T16 = (i8, i8, i8, i8, i8, i8, i8, i8, i8, i8, i8, i8, i8, i8, i8, String);
+        let (out, rest) = T16::unpack(&buf, true).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(out.0, 1);
+        assert_eq!(out.14, 15);
+        assert_eq!(out.15, "sixteen");
+    }
+
+    #[test]
+    fn test_pack_sequence() {
+        let mut buf = Vec::new();
+        test_pack(vec![1, 2], &mut buf, b"\x15\x01\x15\x02");
+        test_pack((vec![1, 2],), &mut buf, b"\x05\x15\x01\x15\x02\x00");
+        test_pack((Vec::<i32>::new(),), &mut buf, b"\x05\x00");
+        test_pack(&["a", "b"][..], &mut buf, b"\x02a\x00\x02b\x00");
+        test_pack(([true, false],), &mut buf, b"\x05\x27\x26\x00");
+        // Sequences of u8 stay byte strings
+        test_pack(vec![1u8, 0], &mut buf, b"\x01\x01\x00\xff\x00");
+        test_pack([1u8, 2], &mut buf, b"\x01\x01\x02\x00");
+        test_pack((vec![vec![0u8]],), &mut buf, b"\x05\x01\x00\xff\x00\x00");
+    }
+
+    #[test]
+    fn test_pack_unpack_sequence() {
+        let mut buf = Vec::new();
+        test_pack_unpack(vec![1, -2, 3], &mut buf);
+        test_pack_unpack((true, vec!["a".to_string(), "b".to_string()]), &mut buf);
+        test_pack_unpack((vec![Vec::<i64>::new(), vec![1]], 2), &mut buf);
+        test_pack_unpack(vec![b"ab".to_vec(), vec![0]], &mut buf);
+        test_pack_unpack(([1u8, 0, 2], [3u16, 4]), &mut buf);
+        test_pack_unpack([0u32; 0], &mut buf);
+
+        buf.clear();
+        (vec![1, 2, 3],).pack(&mut buf, false);
+        assert_eq!(
+            <([i32; 2],)>::unpack(&buf, false),
+            Err(UnpackError::OutOfRange)
+        );
+    }
 }
@@ -12,8 +12,8 @@ impl Pack for Uuid {
     }
 }
 
-impl Unpack for Uuid {
-    fn unpack(inp: &[u8], _nested: bool) -> Result<(Self, &[u8]), UnpackError> {
+impl<'de> Unpack<'de> for Uuid {
+    fn unpack(inp: &'de [u8], _nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
         let inp = expect(inp, UUID_CODE)?;
 
         if inp.len() < size_of::<Bytes>() {
@@ -1,21 +1,264 @@
-use crate::tuple::{Pack, Unpack, UnpackError};
+use crate::tuple::{expect, Pack, Tuple, Unpack, UnpackError};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use std::error;
+use std::fmt::{self, Display, Formatter};
 
-// const VERSIONSTAMP_CODE: u8 = 0x33;
+const VERSIONSTAMP_CODE: u8 = 0x33;
 
-pub struct Versionstamp;
+/*
+ * Versionstamp
+ */
+
+// A 10 byte transaction version, as assigned by the database at commit, followed by a 2 byte
+// version chosen by the user to order multiple versionstamps within one transaction. Incomplete
+// versionstamps have a transaction version of all 0xff, to be filled in by SetVersionstampedKey or
+// SetVersionstampedValue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Versionstamp {
+    bytes: [u8; 12],
+}
+
+impl Versionstamp {
+    pub fn complete(transaction_version: [u8; 10], user_version: u16) -> Self {
+        let mut bytes = [0; 12];
+        bytes[..10].copy_from_slice(&transaction_version);
+        BigEndian::write_u16(&mut bytes[10..], user_version);
+        Versionstamp { bytes }
+    }
+
+    pub fn incomplete(user_version: u16) -> Self {
+        Self::complete([0xff; 10], user_version)
+    }
+
+    pub fn from_bytes(bytes: [u8; 12]) -> Self {
+        Versionstamp { bytes }
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 12] {
+        &self.bytes
+    }
+
+    pub fn transaction_version(&self) -> &[u8] {
+        &self.bytes[..10]
+    }
+
+    pub fn user_version(&self) -> u16 {
+        BigEndian::read_u16(&self.bytes[10..])
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.transaction_version() != [0xff; 10]
+    }
+}
 
 impl Pack for Versionstamp {
-    fn pack(&self, _out: &mut Vec<u8>, _nested: bool) {
-        // TODO: Implement me
-        unimplemented!()
+    fn pack(&self, out: &mut Vec<u8>, _nested: bool) {
+        out.push(VERSIONSTAMP_CODE);
+        out.extend_from_slice(&self.bytes);
+    }
+}
+
+impl<'de> Unpack<'de> for Versionstamp {
+    fn unpack(inp: &'de [u8], _nested: bool) -> Result<(Self, &'de [u8]), UnpackError> {
+        let inp = expect(inp, VERSIONSTAMP_CODE)?;
+
+        if inp.len() < 12 {
+            return Err(UnpackError::OutOfData);
+        }
+
+        let (out, inp) = inp.split_at(12);
+        let mut bytes = [0; 12];
+        bytes.copy_from_slice(out);
+        Ok((Versionstamp { bytes }, inp))
+    }
+}
+
+/*
+ * pack_with_versionstamp
+ */
+
+#[derive(Debug, PartialEq)]
+pub enum PackError {
+    NoIncompleteVersionstamp,
+    MultipleIncompleteVersionstamps,
+    // Something packed itself into bytes that aren't a valid tuple encoding
+    BadEncoding,
+}
+
+impl Display for PackError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            PackError::NoIncompleteVersionstamp => write!(f, "no incomplete versionstamp"),
+            PackError::MultipleIncompleteVersionstamps => {
+                write!(f, "more than one incomplete versionstamp")
+            }
+            PackError::BadEncoding => write!(f, "bad tuple encoding"),
+        }
+    }
+}
+
+impl error::Error for PackError {}
+
+// Skips over the element at the start of inp, adding the offsets of the transaction versions of
+// any incomplete versionstamps in it to found. Offsets are relative to base.
+fn skip_element<'a>(
+    inp: &'a [u8],
+    base: usize,
+    found: &mut Vec<usize>,
+) -> Result<&'a [u8], PackError> {
+    let (&code, rest) = inp.split_first().ok_or(PackError::BadEncoding)?;
+    let len = match code {
+        // Null
+        0x00 => 0,
+        // Byte and unicode strings, terminated by an unescaped null
+        0x01 | 0x02 => {
+            let mut i = 0;
+            loop {
+                match rest.get(i) {
+                    Some(0x00) if rest.get(i + 1) == Some(&0xff) => i += 2,
+                    Some(0x00) => break i + 1,
+                    Some(_) => i += 1,
+                    None => return Err(PackError::BadEncoding),
+                }
+            }
+        }
+        // Nested tuples, where nulls are escaped and a bare null ends the tuple
+        0x05 => {
+            let mut i = 0;
+            loop {
+                match rest.get(i) {
+                    Some(0x00) if rest.get(i + 1) == Some(&0xff) => i += 2,
+                    Some(0x00) => break i + 1,
+                    Some(_) => {
+                        let tail = skip_element(&rest[i..], base + 1 + i, found)?;
+                        i = rest.len() - tail.len();
+                    }
+                    None => return Err(PackError::BadEncoding),
+                }
+            }
+        }
+        // Integers of more than 8 bytes, with a length byte that is inverted for negative ones
+        0x0b => usize::from(!*rest.first().ok_or(PackError::BadEncoding)?) + 1,
+        0x1d => usize::from(*rest.first().ok_or(PackError::BadEncoding)?) + 1,
+        // Integers of up to 8 bytes
+        0x0c..=0x13 => usize::from(0x14 - code),
+        0x14..=0x1c => usize::from(code - 0x14),
+        0x20 => 4,
+        0x21 => 8,
+        0x26 | 0x27 => 0,
+        0x30 => 16,
+        VERSIONSTAMP_CODE => {
+            if rest.len() >= 10 && rest[..10] == [0xff; 10] {
+                found.push(base + 1);
+            }
+            12
+        }
+        _ => return Err(PackError::BadEncoding),
+    };
+
+    if rest.len() < len {
+        return Err(PackError::BadEncoding);
     }
+    Ok(&rest[len..])
 }
 
-impl Unpack for Versionstamp {
-    fn unpack(_inp: &[u8], _nested: bool) -> Result<(Self, &[u8]), UnpackError> {
-        // TODO: Implement me
-        unimplemented!()
+// Packs tuple onto the end of out, followed by the offset of its one incomplete versionstamp, as
+// SetVersionstampedKey and SetVersionstampedValue expect (4 bytes little endian, for API version
+// 520 and newer)
+pub(crate) fn pack_into_with_versionstamp<T>(tuple: &T, out: &mut Vec<u8>) -> Result<(), PackError>
+where
+    T: Tuple + Pack,
+{
+    let start = out.len();
+    tuple.pack(out, false);
+
+    let mut found = Vec::new();
+    let mut inp = &out[start..];
+    while !inp.is_empty() {
+        let offset = out.len() - inp.len();
+        inp = skip_element(inp, offset, &mut found)?;
+    }
+
+    match found.as_slice() {
+        [] => Err(PackError::NoIncompleteVersionstamp),
+        [offset] => {
+            let mut buf = [0; 4];
+            LittleEndian::write_u32(&mut buf, *offset as u32);
+            out.extend_from_slice(&buf);
+            Ok(())
+        }
+        _ => Err(PackError::MultipleIncompleteVersionstamps),
     }
 }
 
-// TODO: Test Versionstamp
+pub fn pack_with_versionstamp<T>(tuple: &T) -> Result<Vec<u8>, PackError>
+where
+    T: Tuple + Pack,
+{
+    let mut out = Vec::new();
+    pack_into_with_versionstamp(tuple, &mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{pack_with_versionstamp, PackError, Versionstamp};
+    use crate::tuple::test::{test_pack, test_pack_unpack};
+
+    #[test]
+    fn test_pack_versionstamp() {
+        let mut buf = Vec::new();
+        let tr_version = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09];
+        test_pack(
+            Versionstamp::complete(tr_version, 0x0a0b),
+            &mut buf,
+            &[0x33, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b],
+        );
+        test_pack(
+            Versionstamp::incomplete(1),
+            &mut buf,
+            &[0x33, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x01],
+        );
+    }
+
+    #[test]
+    fn test_pack_unpack_versionstamp() {
+        let mut buf = Vec::new();
+        test_pack_unpack(Versionstamp::incomplete(42), &mut buf);
+        test_pack_unpack(Versionstamp::complete([7; 10], 42), &mut buf);
+
+        let vs = Versionstamp::complete([7; 10], 42);
+        assert!(vs.is_complete());
+        assert_eq!(vs.transaction_version(), &[7; 10]);
+        assert_eq!(vs.user_version(), 42);
+        assert!(!Versionstamp::incomplete(42).is_complete());
+    }
+
+    #[test]
+    fn test_pack_with_versionstamp() {
+        let packed = pack_with_versionstamp(&("a", Versionstamp::incomplete(3))).unwrap();
+        assert_eq!(
+            packed,
+            &[
+                0x02, b'a', 0x00, 0x33, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0x00, 0x03, 0x04, 0x00, 0x00, 0x00
+            ][..]
+        );
+
+        // Byte strings that merely look like an incomplete versionstamp don't count
+        let decoy = [0x33, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00];
+        let packed =
+            pack_with_versionstamp(&(&decoy[..], (1, Versionstamp::incomplete(0)))).unwrap();
+        let offset = packed.len() - 4 - 1 - 12;
+        assert_eq!(&packed[packed.len() - 4..], &[offset as u8, 0x00, 0x00, 0x00]);
+
+        assert_eq!(
+            pack_with_versionstamp(&(1, Versionstamp::complete([0; 10], 0))),
+            Err(PackError::NoIncompleteVersionstamp)
+        );
+        assert_eq!(
+            pack_with_versionstamp(&(Versionstamp::incomplete(0), Versionstamp::incomplete(1))),
+            Err(PackError::MultipleIncompleteVersionstamps)
+        );
+    }
+}
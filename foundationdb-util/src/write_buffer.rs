@@ -0,0 +1,535 @@
+use foundationdb::{
+    Error, GetRangeOpt, KeySelector, Mutation, MutationType, StreamingMode, Transaction,
+    VALUE_SIZE_LIMIT,
+};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+// accessed_unreadable, as returned by FoundationDB when reading a key with a pending versionstamp
+const ACCESSED_UNREADABLE: i32 = 1036;
+
+// A position in a WriteBuffer's mutations that can be rolled back to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Savepoint(usize);
+
+// Holds mutations client side instead of sending them to the transaction right away, so that
+// those made since a savepoint can be undone. Reads through the buffer see the buffered mutations
+// on top of what the transaction reads. Nothing reaches the transaction until flush is called, so
+// flush before committing.
+pub struct WriteBuffer<'a> {
+    tran: &'a Transaction,
+    buffer: Buffer,
+}
+
+impl<'a> WriteBuffer<'a> {
+    pub fn new(tran: &'a Transaction) -> Self {
+        WriteBuffer {
+            tran,
+            buffer: Buffer::default(),
+        }
+    }
+
+    pub fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.buffer.mutations.push(Mutation::Set {
+            key: key.to_vec(),
+            value: value.to_vec(),
+        });
+    }
+
+    pub fn clear(&mut self, key: &[u8]) {
+        self.buffer
+            .mutations
+            .push(Mutation::Clear { key: key.to_vec() });
+    }
+
+    pub fn clear_range(&mut self, begin_key: &[u8], end_key: &[u8]) {
+        self.buffer.mutations.push(Mutation::ClearRange {
+            begin_key: begin_key.to_vec(),
+            end_key: end_key.to_vec(),
+        });
+    }
+
+    pub fn atomic_op(&mut self, key: &[u8], param: &[u8], mut_type: MutationType) {
+        self.buffer.mutations.push(Mutation::AtomicOp {
+            key: key.to_vec(),
+            param: param.to_vec(),
+            mut_type,
+        });
+    }
+
+    pub fn savepoint(&self) -> Savepoint {
+        self.buffer.savepoint()
+    }
+
+    // Discards every mutation made since the savepoint was taken. Savepoints taken after this one
+    // become invalid.
+    pub fn rollback_to(&mut self, savepoint: Savepoint) {
+        self.buffer.rollback_to(savepoint)
+    }
+
+    pub fn mutations(&self) -> &[Mutation] {
+        &self.buffer.mutations
+    }
+
+    // Sends the buffered mutations to the transaction, in order, and empties the buffer
+    pub fn flush(&mut self) {
+        for mutation in self.buffer.mutations.drain(..) {
+            match mutation {
+                Mutation::Set { key, value } => self.tran.set(&key, &value),
+                Mutation::Clear { key } => self.tran.clear(&key),
+                Mutation::ClearRange { begin_key, end_key } => {
+                    self.tran.clear_range(&begin_key, &end_key)
+                }
+                Mutation::AtomicOp {
+                    key,
+                    param,
+                    mut_type,
+                } => self.tran.atomic_op(&key, &param, mut_type),
+            }
+        }
+    }
+
+    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        self.buffer.get(key, || {
+            Ok(self.tran.get(key, false)?.map(|v| v.as_ref().to_vec()))
+        })
+    }
+
+    // Reads every key in [begin_key, end_key)
+    pub fn get_range(
+        &self,
+        begin_key: &[u8],
+        end_key: &[u8],
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error> {
+        self.buffer.get_range(begin_key, end_key, |next| {
+            let opt = GetRangeOpt {
+                begin_selector: KeySelector::first_greater_or_equal(next),
+                end_selector: KeySelector::first_greater_or_equal(end_key),
+                mode: StreamingMode::WantAll,
+                ..GetRangeOpt::default()
+            };
+            let arr = self.tran.get_range(&opt)?;
+            let kvs = arr
+                .iter()
+                .map(|kv| (kv.key().to_vec(), kv.value().to_vec()))
+                .collect();
+            Ok((kvs, arr.more()))
+        })
+    }
+}
+
+/*
+ * Buffer
+ */
+
+// The mutations of a WriteBuffer and how they change what's read, apart from the transaction
+#[derive(Default)]
+struct Buffer {
+    mutations: Vec<Mutation>,
+}
+
+impl Buffer {
+    fn savepoint(&self) -> Savepoint {
+        Savepoint(self.mutations.len())
+    }
+
+    fn rollback_to(&mut self, savepoint: Savepoint) {
+        debug_assert!(savepoint.0 <= self.mutations.len());
+        self.mutations.truncate(savepoint.0);
+    }
+
+    // The value of key, given read, which reads it from the database. read is only called if the
+    // buffered mutations don't decide the value on their own.
+    fn get<Read>(&self, key: &[u8], read: Read) -> Result<Option<Vec<u8>>, Error>
+    where
+        Read: FnOnce() -> Result<Option<Vec<u8>>, Error>,
+    {
+        // Find the last mutation that decides the value on its own. Only atomic operations after
+        // it need to be applied.
+        let mut start = 0;
+        let mut value = None;
+        for (i, mutation) in self.mutations.iter().enumerate().rev() {
+            match mutation {
+                Mutation::Set { key: k, value: v } if k.as_slice() == key => {
+                    value = Some(Some(v.clone()));
+                }
+                Mutation::Clear { key: k } if k.as_slice() == key => {
+                    value = Some(None);
+                }
+                Mutation::ClearRange { begin_key, end_key }
+                    if begin_key.as_slice() <= key && key < end_key.as_slice() =>
+                {
+                    value = Some(None);
+                }
+                _ => continue,
+            }
+            start = i + 1;
+            break;
+        }
+
+        let mut value = match value {
+            Some(value) => value,
+            None => read()?,
+        };
+
+        for mutation in &self.mutations[start..] {
+            if let Mutation::AtomicOp {
+                key: k,
+                param,
+                mut_type,
+            } = mutation
+            {
+                if k.as_slice() == key {
+                    value = apply_atomic_op(*mut_type, value, param)?;
+                }
+            }
+        }
+
+        Ok(value)
+    }
+
+    // Every key in [begin_key, end_key), given read_page, which reads the keys from the database
+    // starting at the given key, and returns them along with whether there are more
+    fn get_range<ReadPage>(
+        &self,
+        begin_key: &[u8],
+        end_key: &[u8],
+        mut read_page: ReadPage,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error>
+    where
+        ReadPage: FnMut(&[u8]) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, bool), Error>,
+    {
+        let mut kvs = BTreeMap::new();
+        let mut next = begin_key.to_vec();
+        loop {
+            let (page, more) = read_page(&next)?;
+            kvs.extend(page);
+
+            match kvs.keys().next_back() {
+                Some(last) if more => {
+                    next = last.clone();
+                    next.push(0x00);
+                }
+                _ => break,
+            }
+        }
+
+        let in_range = |key: &[u8]| begin_key <= key && key < end_key;
+
+        for mutation in &self.mutations {
+            match mutation {
+                Mutation::Set { key, value } if in_range(key) => {
+                    kvs.insert(key.clone(), value.clone());
+                }
+                Mutation::Clear { key } if in_range(key) => {
+                    kvs.remove(key);
+                }
+                Mutation::ClearRange {
+                    begin_key: clear_begin,
+                    end_key: clear_end,
+                } => {
+                    let cleared = kvs
+                        .range(clear_begin.clone()..)
+                        .map(|(key, _)| key)
+                        .take_while(|key| *key < clear_end)
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    for key in cleared {
+                        kvs.remove(&key);
+                    }
+                }
+                Mutation::AtomicOp {
+                    key,
+                    param,
+                    mut_type,
+                } if in_range(key) => {
+                    let value = apply_atomic_op(*mut_type, kvs.remove(key), param)?;
+                    if let Some(value) = value {
+                        kvs.insert(key.clone(), value);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(kvs.into_iter().collect())
+    }
+}
+
+// Resizes a little endian integer to the length of the parameter, as FoundationDB does
+fn resized(value: Option<Vec<u8>>, len: usize) -> Vec<u8> {
+    let mut value = value.unwrap_or_default();
+    value.resize(len, 0);
+    value
+}
+
+fn compare_le(a: &[u8], b: &[u8]) -> Ordering {
+    a.iter().rev().cmp(b.iter().rev())
+}
+
+// Computes what an atomic operation does to a value, following FoundationDB's semantics
+fn apply_atomic_op(
+    mut_type: MutationType,
+    value: Option<Vec<u8>>,
+    param: &[u8],
+) -> Result<Option<Vec<u8>>, Error> {
+    let value = match mut_type {
+        MutationType::Add => {
+            let mut value = resized(value, param.len());
+            let mut carry = 0u16;
+            for (v, p) in value.iter_mut().zip(param) {
+                let sum = u16::from(*v) + u16::from(*p) + carry;
+                *v = sum as u8;
+                carry = sum >> 8;
+            }
+            Some(value)
+        }
+        MutationType::And | MutationType::BitAnd => match value {
+            Some(value) => {
+                let mut value = resized(Some(value), param.len());
+                value.iter_mut().zip(param).for_each(|(v, p)| *v &= p);
+                Some(value)
+            }
+            None => Some(param.to_vec()),
+        },
+        MutationType::Or | MutationType::BitOr => {
+            let mut value = resized(value, param.len());
+            value.iter_mut().zip(param).for_each(|(v, p)| *v |= p);
+            Some(value)
+        }
+        MutationType::Xor | MutationType::BitXor => {
+            let mut value = resized(value, param.len());
+            value.iter_mut().zip(param).for_each(|(v, p)| *v ^= p);
+            Some(value)
+        }
+        MutationType::AppendIfFits => {
+            let mut value = value.unwrap_or_default();
+            if value.len() + param.len() <= VALUE_SIZE_LIMIT {
+                value.extend_from_slice(param);
+            }
+            Some(value)
+        }
+        MutationType::Max | MutationType::Min => match value {
+            Some(value) => {
+                let value = resized(Some(value), param.len());
+                let ord = compare_le(param, &value);
+                if (mut_type == MutationType::Max) == (ord == Ordering::Greater) {
+                    Some(param.to_vec())
+                } else {
+                    Some(value)
+                }
+            }
+            None => Some(param.to_vec()),
+        },
+        MutationType::ByteMax | MutationType::ByteMin => match value {
+            Some(value) => {
+                let ord = param.cmp(value.as_slice());
+                if (mut_type == MutationType::ByteMax) == (ord == Ordering::Greater) {
+                    Some(param.to_vec())
+                } else {
+                    Some(value)
+                }
+            }
+            None => Some(param.to_vec()),
+        },
+        MutationType::CompareAndClear => match value {
+            Some(ref v) if v.as_slice() == param => None,
+            value => value,
+        },
+        // The key of a versionstamped key isn't known until commit, so it never matches a read
+        MutationType::SetVersionstampedKey => value,
+        MutationType::SetVersionstampedValue => {
+            return Err(Error::from_code(ACCESSED_UNREADABLE));
+        }
+    };
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{apply_atomic_op, Buffer};
+    use foundationdb::{Error, Mutation, MutationType};
+
+    type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn set(key: &[u8], value: &[u8]) -> Mutation {
+        Mutation::Set {
+            key: key.to_vec(),
+            value: value.to_vec(),
+        }
+    }
+
+    fn clear(key: &[u8]) -> Mutation {
+        Mutation::Clear { key: key.to_vec() }
+    }
+
+    fn clear_range(begin_key: &[u8], end_key: &[u8]) -> Mutation {
+        Mutation::ClearRange {
+            begin_key: begin_key.to_vec(),
+            end_key: end_key.to_vec(),
+        }
+    }
+
+    fn add(key: &[u8], param: &[u8]) -> Mutation {
+        Mutation::AtomicOp {
+            key: key.to_vec(),
+            param: param.to_vec(),
+            mut_type: MutationType::Add,
+        }
+    }
+
+    fn kvs(kvs: &[(&[u8], &[u8])]) -> KeyValues {
+        kvs.iter().map(|(k, v)| (k.to_vec(), v.to_vec())).collect()
+    }
+
+    // Reads from a database holding db, which must be sorted, up to page_size keys at a time
+    fn read_pages<'a>(
+        db: &'a [(&'a [u8], &'a [u8])],
+        end_key: &'a [u8],
+        page_size: usize,
+        pages: &'a mut usize,
+    ) -> impl FnMut(&[u8]) -> Result<(KeyValues, bool), Error> + 'a {
+        move |next| {
+            *pages += 1;
+            let left = kvs(db)
+                .into_iter()
+                .filter(|(k, _)| k.as_slice() >= next && k.as_slice() < end_key)
+                .collect::<Vec<_>>();
+            let more = left.len() > page_size;
+            Ok((left.into_iter().take(page_size).collect(), more))
+        }
+    }
+
+    fn not_read() -> Result<Option<Vec<u8>>, Error> {
+        panic!("read the database although the buffer decides the value")
+    }
+
+    #[test]
+    fn test_rollback_to() {
+        let mut buffer = Buffer::default();
+        buffer.mutations.push(set(b"a", b"1"));
+        let savepoint = buffer.savepoint();
+        buffer.mutations.push(set(b"a", b"2"));
+        buffer.mutations.push(clear(b"b"));
+        buffer.mutations.push(set(b"c", b"3"));
+
+        buffer.rollback_to(savepoint);
+        assert_eq!(buffer.mutations, vec![set(b"a", b"1")]);
+        assert_eq!(buffer.get(b"a", not_read), Ok(Some(b"1".to_vec())));
+        assert_eq!(
+            buffer.get(b"b", || Ok(Some(b"db".to_vec()))),
+            Ok(Some(b"db".to_vec()))
+        );
+        assert_eq!(buffer.get(b"c", || Ok(None)), Ok(None));
+
+        // Rolling back to where the buffer already is changes nothing
+        let savepoint = buffer.savepoint();
+        buffer.rollback_to(savepoint);
+        assert_eq!(buffer.mutations, vec![set(b"a", b"1")]);
+    }
+
+    #[test]
+    fn test_get() {
+        let db = || Ok(Some(vec![0x05]));
+
+        let mut buffer = Buffer::default();
+        assert_eq!(buffer.get(b"a", db), Ok(Some(vec![0x05])));
+
+        // Atomic operations apply on top of the database
+        buffer.mutations.push(add(b"a", &[0x01]));
+        assert_eq!(buffer.get(b"a", db), Ok(Some(vec![0x06])));
+
+        // and on top of later sets and clears, without reading the database
+        buffer.mutations.push(set(b"a", &[0x10]));
+        buffer.mutations.push(add(b"a", &[0x01]));
+        assert_eq!(buffer.get(b"a", not_read), Ok(Some(vec![0x11])));
+        buffer.mutations.push(clear(b"a"));
+        assert_eq!(buffer.get(b"a", not_read), Ok(None));
+        buffer.mutations.push(add(b"a", &[0x02]));
+        assert_eq!(buffer.get(b"a", not_read), Ok(Some(vec![0x02])));
+
+        // Clear ranges shadow whatever came before them, for keys in [begin, end) only
+        let mut buffer = Buffer::default();
+        buffer.mutations.push(set(b"b", b"1"));
+        buffer.mutations.push(clear_range(b"a", b"c"));
+        assert_eq!(buffer.get(b"b", not_read), Ok(None));
+        assert_eq!(buffer.get(b"a", not_read), Ok(None));
+        assert_eq!(buffer.get(b"c", db), Ok(Some(vec![0x05])));
+        buffer.mutations.push(add(b"b", &[0x03]));
+        assert_eq!(buffer.get(b"b", not_read), Ok(Some(vec![0x03])));
+    }
+
+    #[test]
+    fn test_get_range() {
+        let db: &[(&[u8], &[u8])] = &[
+            (b"a", b"1"),
+            (b"b", b"2"),
+            (b"c", b"3"),
+            (b"d", b"4"),
+            (b"e", b"5"),
+        ];
+
+        // Pages are read until there are no more
+        let buffer = Buffer::default();
+        let mut pages = 0;
+        let read = read_pages(db, b"e", 2, &mut pages);
+        assert_eq!(buffer.get_range(b"a", b"e", read), Ok(kvs(&db[..4])));
+        assert_eq!(pages, 2);
+
+        // Clear ranges that partly overlap the range, and mutations after them
+        let mut buffer = Buffer::default();
+        buffer.mutations.push(clear_range(b"", b"b"));
+        buffer.mutations.push(clear_range(b"c", b"z"));
+        buffer.mutations.push(set(b"d", b"9"));
+        buffer.mutations.push(add(b"c", &[0x01]));
+        // Outside of the range
+        buffer.mutations.push(set(b"x", b"0"));
+        let mut pages = 0;
+        let read = read_pages(db, b"e", 1, &mut pages);
+        assert_eq!(
+            buffer.get_range(b"a", b"e", read),
+            Ok(kvs(&[(b"b", b"2"), (b"c", &[0x01]), (b"d", b"9")]))
+        );
+        assert_eq!(pages, 4);
+    }
+
+    #[test]
+    fn test_apply_atomic_op() {
+        let add = apply_atomic_op(MutationType::Add, Some(vec![0xff, 0x00]), &[0x01, 0x00]);
+        assert_eq!(add, Ok(Some(vec![0x00, 0x01])));
+
+        let add = apply_atomic_op(MutationType::Add, None, &[0x05]);
+        assert_eq!(add, Ok(Some(vec![0x05])));
+
+        let and = apply_atomic_op(MutationType::BitAnd, None, &[0x0f]);
+        assert_eq!(and, Ok(Some(vec![0x0f])));
+
+        let or = apply_atomic_op(MutationType::BitOr, Some(vec![0xf0, 0x01]), &[0x0f]);
+        assert_eq!(or, Ok(Some(vec![0xff])));
+
+        let max = apply_atomic_op(MutationType::Max, Some(vec![0x00, 0x01]), &[0xff, 0x00]);
+        assert_eq!(max, Ok(Some(vec![0x00, 0x01])));
+
+        let min = apply_atomic_op(MutationType::Min, Some(vec![0x00, 0x01]), &[0xff, 0x00]);
+        assert_eq!(min, Ok(Some(vec![0xff, 0x00])));
+
+        let byte_max = apply_atomic_op(MutationType::ByteMax, Some(b"abc".to_vec()), b"abd");
+        assert_eq!(byte_max, Ok(Some(b"abd".to_vec())));
+
+        let byte_min = apply_atomic_op(MutationType::ByteMin, Some(b"abc".to_vec()), b"abd");
+        assert_eq!(byte_min, Ok(Some(b"abc".to_vec())));
+
+        let append = apply_atomic_op(MutationType::AppendIfFits, Some(b"ab".to_vec()), b"c");
+        assert_eq!(append, Ok(Some(b"abc".to_vec())));
+
+        let cac = apply_atomic_op(MutationType::CompareAndClear, Some(b"x".to_vec()), b"x");
+        assert_eq!(cac, Ok(None));
+
+        let cac = apply_atomic_op(MutationType::CompareAndClear, Some(b"y".to_vec()), b"x");
+        assert_eq!(cac, Ok(Some(b"y".to_vec())));
+
+        let vs = apply_atomic_op(MutationType::SetVersionstampedValue, None, b"x");
+        assert!(vs.is_err());
+    }
+}
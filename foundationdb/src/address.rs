@@ -0,0 +1,204 @@
+use crate::error::Error;
+use crate::outputs::StringArray;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::net::{IpAddr, Ipv6Addr};
+use std::str::FromStr;
+
+/*
+ * StorageAddress
+ */
+
+// The address of a storage server, as returned by get_addresses_for_key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StorageAddress {
+    pub ip: IpAddr,
+    pub port: u16,
+    pub tls: bool,
+}
+
+impl Display for StorageAddress {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match self.ip {
+            IpAddr::V4(ip) => write!(f, "{}:{}", ip, self.port)?,
+            IpAddr::V6(ip) => write!(f, "[{}]:{}", ip, self.port)?,
+        }
+        if self.tls {
+            write!(f, ":tls")?;
+        }
+        Ok(())
+    }
+}
+
+// Parses addresses like 10.0.0.1:4500, 10.0.0.1:4500:tls and [::1]:4500:tls
+impl FromStr for StorageAddress {
+    type Err = AddressParseError;
+
+    fn from_str(s: &str) -> Result<Self, AddressParseError> {
+        let err = || AddressParseError(s.to_string());
+
+        let (ip, rest) = if s.starts_with('[') {
+            let end = s.find(']').ok_or_else(err)?;
+            let ip = Ipv6Addr::from_str(&s[1..end]).map_err(|_| err())?;
+            (IpAddr::V6(ip), &s[end + 1..])
+        } else {
+            let end = s.find(':').ok_or_else(err)?;
+            let ip = IpAddr::from_str(&s[..end]).map_err(|_| err())?;
+            (ip, &s[end..])
+        };
+
+        if !rest.starts_with(':') {
+            return Err(err());
+        }
+        let mut parts = rest[1..].split(':');
+        let port = parts
+            .next()
+            .and_then(|port| u16::from_str(port).ok())
+            .ok_or_else(err)?;
+        let tls = match parts.next() {
+            None => false,
+            Some("tls") => true,
+            Some(_) => return Err(err()),
+        };
+        if parts.next().is_some() {
+            return Err(err());
+        }
+
+        Ok(StorageAddress { ip, port, tls })
+    }
+}
+
+pub(crate) fn parse_addresses(arr: StringArray) -> Result<Vec<StorageAddress>, AddressesError> {
+    arr.iter()
+        .map(|s| s.to_string_lossy().parse().map_err(AddressesError::Parse))
+        .collect()
+}
+
+/*
+ * AddressParseError
+ */
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AddressParseError(String);
+
+impl Display for AddressParseError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "invalid storage address {:?}", self.0)
+    }
+}
+
+impl AddressParseError {
+    // The address that failed to parse, with invalid UTF-8 replaced by U+FFFD
+    pub fn address(&self) -> &str {
+        &self.0
+    }
+}
+
+impl error::Error for AddressParseError {}
+
+/*
+ * AddressesError
+ */
+
+// Returned by get_addresses_for_key, which fails either in FoundationDB or when one of the
+// addresses it got back doesn't parse
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AddressesError {
+    Fdb(Error),
+    Parse(AddressParseError),
+}
+
+impl Display for AddressesError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            AddressesError::Fdb(err) => Display::fmt(err, f),
+            AddressesError::Parse(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl error::Error for AddressesError {}
+
+impl From<Error> for AddressesError {
+    fn from(err: Error) -> Self {
+        AddressesError::Fdb(err)
+    }
+}
+
+// Addresses that don't parse become unknown_error, as FoundationDB has no error for them
+impl From<AddressesError> for Error {
+    fn from(err: AddressesError) -> Self {
+        match err {
+            AddressesError::Fdb(err) => err,
+            AddressesError::Parse(_) => Error { err: 4000 },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AddressParseError, AddressesError, StorageAddress};
+    use crate::error::Error;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    #[test]
+    fn test_parse() {
+        let addr: StorageAddress = "10.0.0.1:4500".parse().unwrap();
+        assert_eq!(addr.ip, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(addr.port, 4500);
+        assert!(!addr.tls);
+
+        let addr: StorageAddress = "10.0.0.1:4500:tls".parse().unwrap();
+        assert_eq!(addr.port, 4500);
+        assert!(addr.tls);
+
+        let addr: StorageAddress = "[::1]:4501:tls".parse().unwrap();
+        assert_eq!(addr.ip, IpAddr::V6(Ipv6Addr::LOCALHOST));
+        assert_eq!(addr.port, 4501);
+        assert!(addr.tls);
+
+        let addr: StorageAddress = "[2001:db8::7]:4500".parse().unwrap();
+        assert_eq!(addr.ip, "2001:db8::7".parse::<IpAddr>().unwrap());
+        assert!(!addr.tls);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for s in &[
+            "",
+            "10.0.0.1",
+            "10.0.0.1:",
+            "10.0.0.1:port",
+            "10.0.0.1:70000",
+            "10.0.0.1:4500:ssl",
+            "10.0.0.1:4500:tls:tls",
+            "::1:4500",
+            "[::1:4500",
+            "[::1]4500",
+            "host:4500",
+        ] {
+            assert_eq!(
+                s.parse::<StorageAddress>(),
+                Err(AddressParseError(s.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_addresses_error() {
+        let err = AddressesError::Parse("host:4500".parse::<StorageAddress>().unwrap_err());
+        assert_eq!(err.to_string(), "invalid storage address \"host:4500\"");
+        assert_eq!(Error::from(err).code(), 4000);
+
+        let err = AddressesError::from(Error::from_code(1020));
+        assert_eq!(Error::from(err).code(), 1020);
+    }
+
+    #[test]
+    fn test_display() {
+        for s in &["10.0.0.1:4500", "10.0.0.1:4500:tls", "[::1]:4500:tls"] {
+            let addr: StorageAddress = s.parse().unwrap();
+            assert_eq!(addr.to_string(), *s);
+        }
+    }
+}
@@ -1,14 +1,20 @@
 use crate::error::Error;
 use crate::options::DatabaseOption;
-use crate::transaction::Transaction;
+use crate::outputs::{KeyValueArray, Value};
+use crate::stats::MetricsSink;
+use crate::transaction::{GetRangeOpt, ReadTransaction, Transaction};
+use crate::transaction_options::TransactionOptions;
 use foundationdb_sys as fdb;
 use std::ffi::{CString, OsStr};
 use std::os::{raw::c_int, unix::ffi::OsStrExt};
 use std::path::Path;
 use std::ptr::null_mut;
+use std::sync::Arc;
 
 pub struct Database {
     pub(crate) database: *mut fdb::FDBDatabase,
+    pub(crate) options: TransactionOptions,
+    pub(crate) metrics: Option<Arc<dyn MetricsSink>>,
 }
 
 impl Database {
@@ -22,7 +28,11 @@ impl Database {
         let cluster_file = CString::new(cluster_file.as_bytes()).unwrap();
         let mut database = null_mut();
         bail!(unsafe { fdb::fdb_create_database(cluster_file.as_ptr(), &mut database) });
-        Ok(Self { database })
+        Ok(Self {
+            database,
+            options: TransactionOptions::default(),
+            metrics: None,
+        })
     }
 
     pub fn set_option(&self, option: DatabaseOption, value: &[u8]) -> Result<(), Error> {
@@ -38,10 +48,101 @@ impl Database {
         Ok(())
     }
 
+    // Sets the options applied to every transaction created with create_transaction
+    pub fn set_transaction_options(&mut self, options: TransactionOptions) {
+        self.options = options;
+    }
+
+    pub fn transaction_options(&self) -> &TransactionOptions {
+        &self.options
+    }
+
+    // Sets the sink that receives the stats of every transaction created from now on
+    pub fn set_metrics_sink(&mut self, sink: Arc<dyn MetricsSink>) {
+        self.metrics = Some(sink);
+    }
+
     pub fn create_transaction(&self) -> Result<Transaction, Error> {
+        self.create_transaction_with(self.options.clone())
+    }
+
+    pub fn create_transaction_with(&self, options: TransactionOptions) -> Result<Transaction, Error> {
         let mut tran = null_mut();
         bail!(unsafe { fdb::fdb_database_create_transaction(self.database, &mut tran) });
-        Ok(Transaction { tran })
+        Transaction::new(tran, options, self.metrics.clone())
+    }
+
+    pub fn create_read_transaction(&self) -> Result<ReadTransaction, Error> {
+        let tran = self.create_transaction()?;
+        Ok(ReadTransaction { tran })
+    }
+
+    // Runs f in a new transaction and commits it, retrying for as long as on_error allows
+    fn retry<F, T>(&self, mut f: F) -> Result<T, Error>
+    where
+        F: FnMut(&Transaction) -> Result<T, Error>,
+    {
+        let mut tran = self.create_transaction()?;
+        loop {
+            let fail_tran = match f(&tran) {
+                Ok(val) => match tran.commit() {
+                    Ok(_) => return Ok(val),
+                    Err(fail_tran) => fail_tran,
+                },
+                Err(err) => tran.into_failed(err),
+            };
+            match fail_tran.on_error() {
+                Ok(reset_tran) => tran = reset_tran,
+                Err(fail_tran) => return Err(fail_tran.into_error()),
+            }
+        }
+    }
+
+    // Runs f in a new read transaction, retrying for as long as on_error allows. There is nothing
+    // to commit.
+    fn retry_read<F, T>(&self, mut f: F) -> Result<T, Error>
+    where
+        F: FnMut(&ReadTransaction) -> Result<T, Error>,
+    {
+        let mut tran = self.create_read_transaction()?;
+        loop {
+            match f(&tran) {
+                Ok(val) => {
+                    tran.commit();
+                    return Ok(val);
+                }
+                Err(err) => tran = tran.on_error(err)?,
+            }
+        }
+    }
+
+    pub fn get(&self, key: &[u8]) -> Result<Option<Value>, Error> {
+        self.retry_read(|tran| tran.get(key, false))
+    }
+
+    pub fn get_range(&self, opt: &GetRangeOpt) -> Result<KeyValueArray, Error> {
+        self.retry_read(|tran| tran.get_range(opt))
+    }
+
+    pub fn set(&self, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        self.retry(|tran| {
+            tran.set(key, value);
+            Ok(())
+        })
+    }
+
+    pub fn clear(&self, key: &[u8]) -> Result<(), Error> {
+        self.retry(|tran| {
+            tran.clear(key);
+            Ok(())
+        })
+    }
+
+    pub fn clear_range(&self, begin_key: &[u8], end_key: &[u8]) -> Result<(), Error> {
+        self.retry(|tran| {
+            tran.clear_range(begin_key, end_key);
+            Ok(())
+        })
     }
 }
 
@@ -50,3 +151,35 @@ impl Drop for Database {
         unsafe { fdb::fdb_database_destroy(self.database) };
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::test::{database, Reports};
+    use crate::{GetRangeOpt, KeySelector, TransactionOutcome};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_reads_skip_commit() {
+        let mut db = database();
+        db.set(b"database_test", b"value").unwrap();
+        let reports = Arc::new(Reports::default());
+        db.set_metrics_sink(reports.clone());
+
+        let value = db.get(b"database_test").unwrap();
+        assert_eq!(value.as_ref().map(|v| v.as_ref()), Some(&b"value"[..]));
+        let opt = GetRangeOpt {
+            begin_selector: KeySelector::first_greater_or_equal(b"database_test"),
+            end_selector: KeySelector::first_greater_than(b"database_test"),
+            ..GetRangeOpt::default()
+        };
+        assert_eq!(db.get_range(&opt).unwrap().len(), 1);
+
+        assert_eq!(
+            reports.outcomes(),
+            vec![TransactionOutcome::Committed, TransactionOutcome::Committed]
+        );
+        for stats in reports.stats() {
+            assert_eq!(stats.commit_latency, None);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use crate::database::Database;
+use crate::error::Error;
+use crate::outputs::{KeyValueArray, Value};
+use crate::transaction::{CommittedTransaction, FailedTransaction, GetRangeOpt, Transaction};
+use futures::future::ready;
+use futures::task::Waker;
+use futures::{self, Future, Poll};
+use std::mem::replace;
+use std::pin::Pin;
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/*
+ * Database
+ */
+
+impl Database {
+    // Runs f in a new transaction and commits it, retrying for as long as on_error allows
+    fn retry_async<'a, F, T>(&'a self, f: F) -> RetryAsync<'a, F, T>
+    where
+        F: FnMut(&Transaction) -> BoxFuture<'a, Result<T, Error>>,
+    {
+        RetryAsync::new(self, false, f)
+    }
+
+    // Like retry_async, but for f that only reads, so there is nothing to commit
+    fn retry_read_async<'a, F, T>(&'a self, f: F) -> RetryAsync<'a, F, T>
+    where
+        F: FnMut(&Transaction) -> BoxFuture<'a, Result<T, Error>>,
+    {
+        RetryAsync::new(self, true, f)
+    }
+
+    pub fn get_async<'a>(
+        &'a self,
+        key: &'a [u8],
+    ) -> impl Future<Output = Result<Option<Value>, Error>> + 'a {
+        self.retry_read_async(move |tran| Box::pin(tran.get_async(key, false)))
+    }
+
+    pub fn get_range_async<'a>(
+        &'a self,
+        opt: &'a GetRangeOpt<'a>,
+    ) -> impl Future<Output = Result<KeyValueArray, Error>> + 'a {
+        self.retry_read_async(move |tran| Box::pin(tran.get_range_async(opt)))
+    }
+
+    pub fn set_async<'a>(
+        &'a self,
+        key: &'a [u8],
+        value: &'a [u8],
+    ) -> impl Future<Output = Result<(), Error>> + 'a {
+        self.retry_async(move |tran| {
+            tran.set(key, value);
+            Box::pin(ready(Ok(())))
+        })
+    }
+
+    pub fn clear_async<'a>(&'a self, key: &'a [u8]) -> impl Future<Output = Result<(), Error>> + 'a {
+        self.retry_async(move |tran| {
+            tran.clear(key);
+            Box::pin(ready(Ok(())))
+        })
+    }
+
+    pub fn clear_range_async<'a>(
+        &'a self,
+        begin_key: &'a [u8],
+        end_key: &'a [u8],
+    ) -> impl Future<Output = Result<(), Error>> + 'a {
+        self.retry_async(move |tran| {
+            tran.clear_range(begin_key, end_key);
+            Box::pin(ready(Ok(())))
+        })
+    }
+}
+
+/*
+ * RetryAsync
+ */
+
+enum State<'a, T> {
+    Running(Transaction, BoxFuture<'a, Result<T, Error>>),
+    Committing(T, BoxFuture<'static, Result<CommittedTransaction, FailedTransaction>>),
+    Resetting(BoxFuture<'static, Result<Transaction, FailedTransaction>>),
+    Failed(Error),
+    Done,
+}
+
+struct RetryAsync<'a, F, T> {
+    f: F,
+    read_only: bool,
+    state: State<'a, T>,
+}
+
+impl<'a, F, T> RetryAsync<'a, F, T>
+where
+    F: FnMut(&Transaction) -> BoxFuture<'a, Result<T, Error>>,
+{
+    fn new(db: &Database, read_only: bool, mut f: F) -> Self {
+        let state = match db.create_transaction() {
+            Ok(tran) => {
+                let fut = f(&tran);
+                State::Running(tran, fut)
+            }
+            Err(err) => State::Failed(err),
+        };
+        RetryAsync {
+            f,
+            read_only,
+            state,
+        }
+    }
+}
+
+// Every future held is boxed, and the value is never pinned
+impl<'a, F, T> Unpin for RetryAsync<'a, F, T> {}
+
+impl<'a, F, T> futures::Future for RetryAsync<'a, F, T>
+where
+    F: FnMut(&Transaction) -> BoxFuture<'a, Result<T, Error>>,
+{
+    type Output = Result<T, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, waker: &Waker) -> Poll<Self::Output> {
+        let this = &mut *self;
+        loop {
+            match replace(&mut this.state, State::Done) {
+                State::Running(tran, mut fut) => match fut.as_mut().poll(waker) {
+                    Poll::Pending => {
+                        this.state = State::Running(tran, fut);
+                        return Poll::Pending;
+                    }
+                    Poll::Ready(Ok(val)) if this.read_only => {
+                        tran.end_read_only();
+                        return Poll::Ready(Ok(val));
+                    }
+                    Poll::Ready(Ok(val)) => {
+                        this.state = State::Committing(val, Box::pin(tran.commit_async()));
+                    }
+                    Poll::Ready(Err(err)) => {
+                        let fut = tran.into_failed(err).on_error_async();
+                        this.state = State::Resetting(Box::pin(fut));
+                    }
+                },
+                State::Committing(val, mut fut) => match fut.as_mut().poll(waker) {
+                    Poll::Pending => {
+                        this.state = State::Committing(val, fut);
+                        return Poll::Pending;
+                    }
+                    Poll::Ready(Ok(_)) => return Poll::Ready(Ok(val)),
+                    Poll::Ready(Err(fail_tran)) => {
+                        this.state = State::Resetting(Box::pin(fail_tran.on_error_async()));
+                    }
+                },
+                State::Resetting(mut fut) => match fut.as_mut().poll(waker) {
+                    Poll::Pending => {
+                        this.state = State::Resetting(fut);
+                        return Poll::Pending;
+                    }
+                    Poll::Ready(Ok(tran)) => {
+                        let fut = (this.f)(&tran);
+                        this.state = State::Running(tran, fut);
+                    }
+                    Poll::Ready(Err(fail_tran)) => return Poll::Ready(Err(fail_tran.into_error())),
+                },
+                State::Failed(err) => return Poll::Ready(Err(err)),
+                State::Done => panic!("RetryAsync polled after completion"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::{database, Reports};
+    use crate::TransactionOutcome;
+    use futures::executor::block_on;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_reads_skip_commit_async() {
+        let mut db = database();
+        db.set(b"database_async_test", b"value").unwrap();
+        let reports = Arc::new(Reports::default());
+        db.set_metrics_sink(reports.clone());
+
+        let value = block_on(db.get_async(b"database_async_test")).unwrap();
+        assert_eq!(value.as_ref().map(|v| v.as_ref()), Some(&b"value"[..]));
+
+        assert_eq!(reports.outcomes(), vec![TransactionOutcome::Committed]);
+        assert_eq!(reports.stats()[0].commit_latency, None);
+    }
+}
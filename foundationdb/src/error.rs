@@ -1,12 +1,29 @@
+use crate::options::ErrorPredicate;
 use foundationdb_sys as fdb;
 use std::error;
 use std::ffi::CStr;
 use std::fmt::{self, Debug, Display, Formatter};
+use std::os::raw::c_int;
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct Error {
     pub(crate) err: fdb::fdb_error_t,
 }
 
+impl Error {
+    pub fn from_code(code: i32) -> Self {
+        Error { err: code }
+    }
+
+    pub fn code(&self) -> i32 {
+        self.err
+    }
+
+    pub fn is(&self, predicate: ErrorPredicate) -> bool {
+        unsafe { fdb::fdb_error_predicate(predicate.as_c_enum() as c_int, self.err) != 0 }
+    }
+}
+
 impl Debug for Error {
     fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
         let err_str = unsafe { CStr::from_ptr(fdb::fdb_get_error(self.err)) };
@@ -65,6 +65,13 @@ impl FutureReady {
         Ok(version)
     }
 
+    #[cfg(feature = "fdb-6_2")]
+    pub fn into_int64(self) -> Result<i64, Error> {
+        let mut val = 0;
+        bail!(unsafe { fdb::fdb_future_get_int64(self.fut, &mut val) });
+        Ok(val)
+    }
+
     pub fn into_string_array(mut self) -> Result<StringArray, Error> {
         let mut strings = null_mut();
         let mut count = 0;
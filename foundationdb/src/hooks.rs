@@ -0,0 +1,37 @@
+use crate::error::Error;
+
+type CommitHook = Box<dyn FnOnce(i64, Option<&[u8]>)>;
+type AbortHook = Box<dyn FnOnce(Option<Error>)>;
+
+// Callbacks registered on a transaction, to be run once its final outcome is known
+#[derive(Default)]
+pub(crate) struct Hooks {
+    on_commit: Vec<CommitHook>,
+    on_abort: Vec<AbortHook>,
+}
+
+impl Hooks {
+    pub(crate) fn add_on_commit(&mut self, hook: CommitHook) {
+        self.on_commit.push(hook);
+    }
+
+    pub(crate) fn add_on_abort(&mut self, hook: AbortHook) {
+        self.on_abort.push(hook);
+    }
+
+    pub(crate) fn has_on_commit(&self) -> bool {
+        !self.on_commit.is_empty()
+    }
+
+    pub(crate) fn run_on_commit(self, version: i64, versionstamp: Option<&[u8]>) {
+        for hook in self.on_commit {
+            hook(version, versionstamp);
+        }
+    }
+
+    pub(crate) fn run_on_abort(self, err: Option<Error>) {
+        for hook in self.on_abort {
+            hook(err);
+        }
+    }
+}
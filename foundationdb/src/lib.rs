@@ -10,23 +10,86 @@ macro_rules! bail {
     }};
 }
 
+mod address;
 mod database;
+#[cfg(feature = "async")]
+mod database_async;
 mod error;
 mod future;
 #[cfg(feature = "async")]
 mod future_async;
 mod future_ready;
+mod hooks;
+mod mutation;
 mod network;
 mod options;
 mod outputs;
+#[cfg(feature = "bytes")]
+mod outputs_bytes;
+mod printable;
+mod size;
+mod stats;
+mod trace;
 mod transaction;
 #[cfg(feature = "async")]
 mod transaction_async;
+mod transaction_options;
 
 // Everything is public except futures
+pub use address::{AddressParseError, AddressesError, StorageAddress};
 pub use database::*;
 pub use error::*;
+pub use mutation::Mutation;
 pub use network::*;
 pub use options::*;
 pub use outputs::*;
+pub use printable::*;
+pub use size::*;
+pub use stats::{MetricsSink, TransactionOutcome, TransactionStats};
 pub use transaction::*;
+pub use transaction_options::*;
+
+#[cfg(test)]
+mod test {
+    use crate::{Database, MetricsSink, Network, TransactionOutcome, TransactionStats};
+    use std::mem;
+    use std::sync::{Mutex, Once};
+
+    // FDB lets a process set up its network only once, and can't restart it once stopped, so
+    // every test shares one that runs until the process exits
+    pub(crate) fn database() -> Database {
+        static NETWORK: Once = Once::new();
+        NETWORK.call_once(|| mem::forget(Network::new().unwrap()));
+        Database::new().unwrap()
+    }
+
+    // Keeps everything reported to it, in order
+    #[derive(Default)]
+    pub(crate) struct Reports(Mutex<Vec<(TransactionStats, TransactionOutcome)>>);
+
+    impl Reports {
+        pub(crate) fn stats(&self) -> Vec<TransactionStats> {
+            self.0
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(stats, _)| *stats)
+                .collect()
+        }
+
+        pub(crate) fn outcomes(&self) -> Vec<TransactionOutcome> {
+            self.0
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(_, outcome)| *outcome)
+                .collect()
+        }
+    }
+
+    impl MetricsSink for Reports {
+        fn record(&self, stats: &TransactionStats, outcome: TransactionOutcome) {
+            self.0.lock().unwrap().push((*stats, outcome));
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::options::MutationType;
+use std::cell::RefCell;
+use std::mem::replace;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Mutation {
+    Set {
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+    Clear {
+        key: Vec<u8>,
+    },
+    ClearRange {
+        begin_key: Vec<u8>,
+        end_key: Vec<u8>,
+    },
+    AtomicOp {
+        key: Vec<u8>,
+        param: Vec<u8>,
+        mut_type: MutationType,
+    },
+}
+
+// Keeps the mutations made to a transaction in order, once recording has been started
+#[derive(Default)]
+pub(crate) struct Recorder {
+    mutations: RefCell<Option<Vec<Mutation>>>,
+}
+
+impl Recorder {
+    pub(crate) fn start(&self) {
+        let mut mutations = self.mutations.borrow_mut();
+        if mutations.is_none() {
+            *mutations = Some(Vec::new());
+        }
+    }
+
+    pub(crate) fn record<F>(&self, f: F)
+    where
+        F: FnOnce() -> Mutation,
+    {
+        if let Some(mutations) = self.mutations.borrow_mut().as_mut() {
+            mutations.push(f());
+        }
+    }
+
+    pub(crate) fn get(&self) -> Vec<Mutation> {
+        self.mutations.borrow().clone().unwrap_or_default()
+    }
+
+    pub(crate) fn take(&self) -> Vec<Mutation> {
+        self.mutations
+            .borrow_mut()
+            .as_mut()
+            .map(|mutations| replace(mutations, Vec::new()))
+            .unwrap_or_default()
+    }
+
+    // Forgets what was recorded, but keeps recording if it was
+    pub(crate) fn reset(&self) {
+        if let Some(mutations) = self.mutations.borrow_mut().as_mut() {
+            mutations.clear();
+        }
+    }
+}
@@ -1,7 +1,7 @@
 // Generated by gen_options, do not edit
 use foundationdb_sys as fdb;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum NetworkOption {
     LocalAddress,
     ClusterFile,
@@ -69,7 +69,7 @@ impl NetworkOption {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DatabaseOption {
     LocationCacheSize,
     MaxWatches,
@@ -89,7 +89,7 @@ impl DatabaseOption {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TransactionOption {
     CausalWriteRisky,
     CausalReadRisky,
@@ -149,7 +149,7 @@ impl TransactionOption {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum StreamingMode {
     WantAll,
     Iterator,
@@ -175,7 +175,7 @@ impl StreamingMode {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MutationType {
     Add,
     And,
@@ -217,7 +217,7 @@ impl MutationType {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ConflictRangeType {
     Read,
     Write,
@@ -233,7 +233,7 @@ impl ConflictRangeType {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ErrorPredicate {
     Retryable,
     MaybeCommitted,
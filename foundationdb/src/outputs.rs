@@ -1,7 +1,9 @@
+use crate::printable::Printable;
 use foundationdb_sys as fdb;
 use std::borrow::Cow;
 use std::ffi::CStr;
-use std::mem::size_of;
+use std::fmt::{self, Debug, Formatter};
+use std::ops::Index;
 use std::os::raw::{c_char, c_int, c_void};
 use std::slice;
 
@@ -21,6 +23,12 @@ impl AsRef<[u8]> for Key {
     }
 }
 
+impl Debug for Key {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        Debug::fmt(&Printable(self.as_ref()), f)
+    }
+}
+
 impl Drop for Key {
     fn drop(&mut self) {
         unsafe { fdb::fdb_future_destroy(self.fut) };
@@ -43,6 +51,12 @@ impl AsRef<[u8]> for Value {
     }
 }
 
+impl Debug for Value {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        Debug::fmt(&Printable(self.as_ref()), f)
+    }
+}
+
 impl Drop for Value {
     fn drop(&mut self) {
         unsafe { fdb::fdb_future_destroy(self.fut) };
@@ -61,21 +75,38 @@ struct RawKeyValue {
     value_len: c_int,
 }
 
-pub struct KeyValue<'a> {
-    kv: &'a fdb::FDBKeyValue,
+// Only ever borrowed from the KeyValueArray holding it
+#[repr(transparent)]
+pub struct KeyValue {
+    kv: fdb::FDBKeyValue,
 }
 
 #[allow(clippy::cast_ptr_alignment)]
-impl<'a> KeyValue<'a> {
+impl KeyValue {
     pub fn key(&self) -> &[u8] {
-        let kv = self.kv as *const _ as *const RawKeyValue;
+        let kv = &self.kv as *const _ as *const RawKeyValue;
         unsafe { slice::from_raw_parts((*kv).key as *const _, (*kv).key_len as usize) }
     }
 
     pub fn value(&self) -> &[u8] {
-        let kv = self.kv as *const _ as *const RawKeyValue;
+        let kv = &self.kv as *const _ as *const RawKeyValue;
         unsafe { slice::from_raw_parts((*kv).value as *const _, (*kv).value_len as usize) }
     }
+
+    // Copies the key and the value out of the array
+    #[allow(clippy::wrong_self_convention)]
+    pub fn into_owned(&self) -> (Vec<u8>, Vec<u8>) {
+        (self.key().to_vec(), self.value().to_vec())
+    }
+}
+
+impl Debug for KeyValue {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("KeyValue")
+            .field("key", &Printable(self.key()))
+            .field("value", &Printable(self.value()))
+            .finish()
+    }
 }
 
 /*
@@ -90,11 +121,16 @@ pub struct KeyValueArray {
 }
 
 impl KeyValueArray {
-    pub fn get(&self, index: usize) -> KeyValue {
-        // TODO: This assert should be in a test
-        debug_assert_eq!(size_of::<RawKeyValue>(), size_of::<fdb::FDBKeyValue>());
-        KeyValue {
-            kv: unsafe { &*self.kv.add(index) },
+    // Panics if index is out of range
+    pub fn get(&self, index: usize) -> &KeyValue {
+        &self[index]
+    }
+
+    pub fn try_get(&self, index: usize) -> Option<&KeyValue> {
+        if index < self.len() {
+            Some(unsafe { &*(self.kv.add(index) as *const KeyValue) })
+        } else {
+            None
         }
     }
 
@@ -103,7 +139,7 @@ impl KeyValueArray {
     }
 
     pub fn is_empty(&self) -> bool {
-        self.count != 0
+        self.count == 0
     }
 
     pub fn more(&self) -> bool {
@@ -113,6 +149,41 @@ impl KeyValueArray {
     pub fn iter(&self) -> KeyValueArrayIter {
         KeyValueArrayIter { arr: self, i: 0 }
     }
+
+    // Copies every key and value out of the array
+    pub fn to_vec(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.iter().map(KeyValue::into_owned).collect()
+    }
+}
+
+impl Index<usize> for KeyValueArray {
+    type Output = KeyValue;
+
+    fn index(&self, index: usize) -> &KeyValue {
+        match self.try_get(index) {
+            Some(kv) => kv,
+            None => panic!(
+                "index {} out of range for KeyValueArray of length {}",
+                index,
+                self.len()
+            ),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a KeyValueArray {
+    type Item = &'a KeyValue;
+    type IntoIter = KeyValueArrayIter<'a>;
+
+    fn into_iter(self) -> KeyValueArrayIter<'a> {
+        self.iter()
+    }
+}
+
+impl Debug for KeyValueArray {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        f.debug_list().entries(self.iter()).finish()
+    }
 }
 
 impl Drop for KeyValueArray {
@@ -127,15 +198,11 @@ pub struct KeyValueArrayIter<'a> {
 }
 
 impl<'a> Iterator for KeyValueArrayIter<'a> {
-    type Item = KeyValue<'a>;
+    type Item = &'a KeyValue;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.i < self.arr.len() {
-            let i = self.i;
-            self.i += 1;
-            Some(self.arr.get(i))
-        } else {
-            None
-        }
+        let kv = self.arr.try_get(self.i)?;
+        self.i += 1;
+        Some(kv)
     }
 }
 
@@ -150,9 +217,17 @@ pub struct StringArray {
 }
 
 impl StringArray {
+    // Replaces invalid UTF-8 with U+FFFD, and panics if index is out of range
     pub fn get(&self, index: usize) -> Cow<str> {
-        let s = unsafe { CStr::from_ptr(*self.strings.add(index)) };
-        s.to_string_lossy()
+        self[index].to_string_lossy()
+    }
+
+    pub fn get_cstr(&self, index: usize) -> Option<&CStr> {
+        if index < self.len() {
+            Some(unsafe { CStr::from_ptr(*self.strings.add(index)) })
+        } else {
+            None
+        }
     }
 
     pub fn len(&self) -> usize {
@@ -160,7 +235,43 @@ impl StringArray {
     }
 
     pub fn is_empty(&self) -> bool {
-        self.count != 0
+        self.count == 0
+    }
+
+    pub fn iter(&self) -> StringArrayIter {
+        StringArrayIter { arr: self, i: 0 }
+    }
+}
+
+impl Index<usize> for StringArray {
+    type Output = CStr;
+
+    fn index(&self, index: usize) -> &CStr {
+        match self.get_cstr(index) {
+            Some(s) => s,
+            None => panic!(
+                "index {} out of range for StringArray of length {}",
+                index,
+                self.len()
+            ),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a StringArray {
+    type Item = &'a CStr;
+    type IntoIter = StringArrayIter<'a>;
+
+    fn into_iter(self) -> StringArrayIter<'a> {
+        self.iter()
+    }
+}
+
+impl Debug for StringArray {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        f.debug_list()
+            .entries(self.iter().map(|s| Printable(s.to_bytes())))
+            .finish()
     }
 }
 
@@ -169,3 +280,127 @@ impl Drop for StringArray {
         unsafe { fdb::fdb_future_destroy(self.fut) };
     }
 }
+
+pub struct StringArrayIter<'a> {
+    arr: &'a StringArray,
+    i: usize,
+}
+
+impl<'a> Iterator for StringArrayIter<'a> {
+    type Item = &'a CStr;
+    fn next(&mut self) -> Option<Self::Item> {
+        let s = self.arr.get_cstr(self.i)?;
+        self.i += 1;
+        Some(s)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{KeyValue, RawKeyValue};
+    use crate::future::Future;
+    use crate::test::database;
+    use crate::{GetRangeOpt, KeySelector};
+    use foundationdb_sys as fdb;
+    use std::ffi::CStr;
+    use std::mem::size_of;
+    use std::os::raw::c_int;
+
+    fn range_opt<'a>(begin: &'a [u8], end: &'a [u8]) -> GetRangeOpt<'a> {
+        GetRangeOpt {
+            begin_selector: KeySelector::first_greater_or_equal(begin),
+            end_selector: KeySelector::first_greater_or_equal(end),
+            ..GetRangeOpt::default()
+        }
+    }
+
+    #[test]
+    fn test_raw_key_value() {
+        assert_eq!(size_of::<RawKeyValue>(), size_of::<fdb::FDBKeyValue>());
+        assert_eq!(size_of::<KeyValue>(), size_of::<fdb::FDBKeyValue>());
+    }
+
+    #[test]
+    fn test_key_value_array() {
+        let db = database();
+        db.clear_range(b"outputs_test/", b"outputs_test0").unwrap();
+
+        let empty = db
+            .get_range(&range_opt(b"outputs_test/", b"outputs_test0"))
+            .unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.len(), 0);
+        assert!(empty.try_get(0).is_none());
+        assert_eq!(empty.iter().count(), 0);
+        assert!(empty.to_vec().is_empty());
+
+        // Written out of order, as the array is sorted by key regardless
+        let expected = vec![
+            (b"outputs_test/a".to_vec(), b"1".to_vec()),
+            (b"outputs_test/b".to_vec(), b"".to_vec()),
+            (b"outputs_test/c".to_vec(), b"3".to_vec()),
+        ];
+        for (key, value) in expected.iter().rev() {
+            db.set(key, value).unwrap();
+        }
+
+        let arr = db
+            .get_range(&range_opt(b"outputs_test/", b"outputs_test0"))
+            .unwrap();
+        assert!(!arr.is_empty());
+        assert_eq!(arr.len(), 3);
+        for (i, (key, value)) in expected.iter().enumerate() {
+            assert_eq!(arr[i].key(), &key[..]);
+            assert_eq!(arr[i].value(), &value[..]);
+            assert_eq!(arr.get(i).key(), &key[..]);
+            assert_eq!(
+                arr.try_get(i).map(KeyValue::into_owned).as_ref(),
+                Some(&expected[i])
+            );
+        }
+        assert!(arr.try_get(3).is_none());
+        assert_eq!(
+            arr.iter().map(KeyValue::into_owned).collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!((&arr).into_iter().count(), 3);
+        assert_eq!(arr.to_vec(), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "index 0 out of range for KeyValueArray of length 0")]
+    fn test_key_value_array_out_of_range() {
+        let db = database();
+        db.clear_range(b"outputs_empty/", b"outputs_empty0")
+            .unwrap();
+        let arr = db
+            .get_range(&range_opt(b"outputs_empty/", b"outputs_empty0"))
+            .unwrap();
+        let _ = &arr[0];
+    }
+
+    #[test]
+    fn test_string_array() {
+        let db = database();
+        let tran = db.create_transaction().unwrap();
+        let key = b"outputs_test";
+        let fut = unsafe {
+            fdb::fdb_transaction_get_addresses_for_key(tran.tran, key.as_ptr(), key.len() as c_int)
+        };
+        let arr = Future::new(fut)
+            .block_until_ready()
+            .and_then(|fut| fut.into_string_array())
+            .unwrap();
+
+        assert!(!arr.is_empty());
+        let strings: Vec<&CStr> = arr.iter().collect();
+        assert_eq!(strings.len(), arr.len());
+        assert_eq!((&arr).into_iter().count(), arr.len());
+        for (i, s) in strings.iter().enumerate() {
+            assert_eq!(&arr[i], *s);
+            assert_eq!(arr.get_cstr(i), Some(*s));
+            assert_eq!(arr.get(i), s.to_string_lossy());
+        }
+        assert!(arr.get_cstr(arr.len()).is_none());
+    }
+}
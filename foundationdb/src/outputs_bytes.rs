@@ -0,0 +1,88 @@
+use crate::outputs::{Key, KeyValueArray, Value};
+use bytes::Bytes;
+
+// Bytes can only share memory it allocated itself, so these copy out of the FDB future once,
+// and the future is freed as soon as the conversion returns
+
+impl From<Key> for Bytes {
+    fn from(key: Key) -> Self {
+        Bytes::from(key.as_ref())
+    }
+}
+
+impl From<Value> for Bytes {
+    fn from(value: Value) -> Self {
+        Bytes::from(value.as_ref())
+    }
+}
+
+impl KeyValueArray {
+    // Copies every key and value into one buffer that all of the returned Bytes share, rather
+    // than allocating one per key and value
+    pub fn into_bytes(self) -> Vec<(Bytes, Bytes)> {
+        let len = self
+            .iter()
+            .map(|kv| kv.key().len() + kv.value().len())
+            .sum();
+        let mut buf = Vec::with_capacity(len);
+        for kv in &self {
+            buf.extend_from_slice(kv.key());
+            buf.extend_from_slice(kv.value());
+        }
+
+        let buf = Bytes::from(buf);
+        let mut begin = 0;
+        self.iter()
+            .map(|kv| {
+                let mid = begin + kv.key().len();
+                let end = mid + kv.value().len();
+                let entry = (buf.slice(begin, mid), buf.slice(mid, end));
+                begin = end;
+                entry
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::database;
+    use crate::{GetRangeOpt, KeySelector};
+    use bytes::Bytes;
+
+    #[test]
+    fn test_into_bytes() {
+        let db = database();
+        db.set(b"bytes_test/a", b"1").unwrap();
+        db.set(b"bytes_test/b", b"").unwrap();
+        db.set(b"bytes_test/c", b"333").unwrap();
+
+        let tran = db.create_transaction().unwrap();
+        let key = tran
+            .get_key(KeySelector::first_greater_or_equal(b"bytes_test/"), false)
+            .unwrap();
+        let value = tran.get(b"bytes_test/c", false).unwrap().unwrap();
+        let opt = GetRangeOpt {
+            begin_selector: KeySelector::first_greater_or_equal(b"bytes_test/"),
+            end_selector: KeySelector::first_greater_or_equal(b"bytes_test0"),
+            ..GetRangeOpt::default()
+        };
+        let arr = tran.get_range(&opt).unwrap();
+        let expected = arr.to_vec();
+        assert_eq!(expected.len(), 3);
+
+        // Converting consumes the outputs, and the Bytes outlive them and the transaction
+        let key = Bytes::from(key);
+        let value = Bytes::from(value);
+        let kvs = arr.into_bytes();
+        drop(tran);
+
+        assert_eq!(&key[..], b"bytes_test/a");
+        assert_eq!(&value[..], b"333");
+        assert_eq!(kvs.len(), expected.len());
+        for ((key, value), (expected_key, expected_value)) in kvs.iter().zip(&expected) {
+            assert_eq!(&key[..], &expected_key[..]);
+            assert_eq!(&value[..], &expected_value[..]);
+        }
+    }
+}
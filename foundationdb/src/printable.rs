@@ -0,0 +1,45 @@
+use std::fmt::{self, Debug, Display, Formatter};
+
+// Formats bytes the way fdbcli does: printable ASCII is shown as is, backslashes are doubled, and
+// everything else is written as \xNN
+pub struct Printable<'a>(pub &'a [u8]);
+
+impl<'a> Display for Printable<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        for &b in self.0 {
+            if b == b'\\' {
+                write!(f, "\\\\")?;
+            } else if (32..127).contains(&b) {
+                write!(f, "{}", b as char)?;
+            } else {
+                write!(f, "\\x{:02x}", b)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Debug for Printable<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "\"{}\"", self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Printable;
+
+    #[test]
+    fn test_printable() {
+        assert_eq!(Printable(b"hello").to_string(), "hello");
+        assert_eq!(Printable(b"a b~").to_string(), "a b~");
+        assert_eq!(Printable(b"\\").to_string(), "\\\\");
+        assert_eq!(
+            Printable(b"\x00\x1f\x7f\xff").to_string(),
+            "\\x00\\x1f\\x7f\\xff"
+        );
+        assert_eq!(Printable(b"\x02users\x00").to_string(), "\\x02users\\x00");
+        assert_eq!(Printable(b"").to_string(), "");
+        assert_eq!(format!("{:?}", Printable(b"a\x01")), "\"a\\x01\"");
+    }
+}
@@ -0,0 +1,228 @@
+use crate::error::Error;
+use crate::printable::Printable;
+use std::cell::Cell;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+
+pub const KEY_SIZE_LIMIT: usize = 10_000;
+pub const VALUE_SIZE_LIMIT: usize = 100_000;
+pub const TRANSACTION_SIZE_LIMIT: usize = 10_000_000;
+
+// The FoundationDB errors for the limits above
+const TRANSACTION_TOO_LARGE: i32 = 2101;
+const KEY_TOO_LARGE: i32 = 2102;
+const VALUE_TOO_LARGE: i32 = 2103;
+
+// A client side estimate of what a transaction will send when committed
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TransactionSize {
+    pub mutation_bytes: usize,
+    pub conflict_range_bytes: usize,
+}
+
+impl TransactionSize {
+    pub fn total(&self) -> usize {
+        self.mutation_bytes + self.conflict_range_bytes
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct SizeTracker {
+    size: Cell<TransactionSize>,
+}
+
+impl SizeTracker {
+    pub(crate) fn get(&self) -> TransactionSize {
+        self.size.get()
+    }
+
+    pub(crate) fn reset(&self) {
+        self.size.set(TransactionSize::default());
+    }
+
+    pub(crate) fn add(&self, mutation_bytes: usize, conflict_range_bytes: usize) {
+        let mut size = self.size.get();
+        size.mutation_bytes += mutation_bytes;
+        size.conflict_range_bytes += conflict_range_bytes;
+        self.size.set(size);
+    }
+
+    // Writing a single key adds the write conflict range [key, key + \x00)
+    pub(crate) fn add_key_write(&self, key: &[u8], param: &[u8]) {
+        self.add(key.len() + param.len(), key.len() * 2 + 1);
+    }
+
+    pub(crate) fn add_range_write(&self, begin_key: &[u8], end_key: &[u8]) {
+        self.add(begin_key.len() + end_key.len(), begin_key.len() + end_key.len());
+    }
+
+    pub(crate) fn check(
+        &self,
+        key: &[u8],
+        value: Option<&[u8]>,
+        added: usize,
+    ) -> Result<(), SizeLimitError> {
+        if key.len() > KEY_SIZE_LIMIT {
+            return Err(SizeLimitError::KeyTooLarge {
+                key: key.to_vec(),
+                size: key.len(),
+            });
+        }
+
+        if let Some(value) = value {
+            if value.len() > VALUE_SIZE_LIMIT {
+                return Err(SizeLimitError::ValueTooLarge {
+                    key: key.to_vec(),
+                    size: value.len(),
+                });
+            }
+        }
+
+        let size = self.get().total() + added;
+        if size > TRANSACTION_SIZE_LIMIT {
+            return Err(SizeLimitError::TransactionTooLarge {
+                key: key.to_vec(),
+                size,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+// Returned by the try_ variants of the write operations when a write would exceed one of
+// FoundationDB's size limits. Each variant holds the key being written and the size that was too
+// large.
+#[derive(Debug)]
+pub enum SizeLimitError {
+    KeyTooLarge { key: Vec<u8>, size: usize },
+    ValueTooLarge { key: Vec<u8>, size: usize },
+    TransactionTooLarge { key: Vec<u8>, size: usize },
+}
+
+impl SizeLimitError {
+    pub fn key(&self) -> &[u8] {
+        match self {
+            SizeLimitError::KeyTooLarge { key, .. } => key,
+            SizeLimitError::ValueTooLarge { key, .. } => key,
+            SizeLimitError::TransactionTooLarge { key, .. } => key,
+        }
+    }
+}
+
+impl Display for SizeLimitError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            SizeLimitError::KeyTooLarge { key, size } => write!(
+                f,
+                "Key of {} bytes exceeds the limit of {}: {}",
+                size,
+                KEY_SIZE_LIMIT,
+                Printable(key)
+            ),
+            SizeLimitError::ValueTooLarge { key, size } => write!(
+                f,
+                "Value of {} bytes exceeds the limit of {} for key {}",
+                size,
+                VALUE_SIZE_LIMIT,
+                Printable(key)
+            ),
+            SizeLimitError::TransactionTooLarge { key, size } => write!(
+                f,
+                "Transaction of {} bytes would exceed the limit of {} when writing key {}",
+                size,
+                TRANSACTION_SIZE_LIMIT,
+                Printable(key)
+            ),
+        }
+    }
+}
+
+impl error::Error for SizeLimitError {}
+
+impl From<SizeLimitError> for Error {
+    fn from(err: SizeLimitError) -> Self {
+        let err = match err {
+            SizeLimitError::KeyTooLarge { .. } => KEY_TOO_LARGE,
+            SizeLimitError::ValueTooLarge { .. } => VALUE_TOO_LARGE,
+            SizeLimitError::TransactionTooLarge { .. } => TRANSACTION_TOO_LARGE,
+        };
+        Error { err }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        SizeLimitError, SizeTracker, KEY_SIZE_LIMIT, TRANSACTION_SIZE_LIMIT, VALUE_SIZE_LIMIT,
+    };
+    use crate::error::Error;
+
+    #[test]
+    fn test_key_limit() {
+        let size = SizeTracker::default();
+        let key = vec![b'k'; KEY_SIZE_LIMIT];
+        assert!(size.check(&key, None, 0).is_ok());
+
+        let key = vec![b'k'; KEY_SIZE_LIMIT + 1];
+        match size.check(&key, None, 0) {
+            Err(SizeLimitError::KeyTooLarge { key: k, size }) => {
+                assert_eq!(k, key);
+                assert_eq!(size, KEY_SIZE_LIMIT + 1);
+            }
+            res => panic!("unexpected {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_value_limit() {
+        let size = SizeTracker::default();
+        let value = vec![b'v'; VALUE_SIZE_LIMIT];
+        assert!(size.check(b"key", Some(&value), 0).is_ok());
+
+        let value = vec![b'v'; VALUE_SIZE_LIMIT + 1];
+        match size.check(b"key", Some(&value), 0) {
+            Err(SizeLimitError::ValueTooLarge { key, size }) => {
+                assert_eq!(key, b"key");
+                assert_eq!(size, VALUE_SIZE_LIMIT + 1);
+            }
+            res => panic!("unexpected {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_transaction_limit() {
+        let size = SizeTracker::default();
+        size.add(TRANSACTION_SIZE_LIMIT - 100, 0);
+        assert!(size.check(b"key", None, 100).is_ok());
+
+        match size.check(b"key", None, 101) {
+            Err(SizeLimitError::TransactionTooLarge { key, size }) => {
+                assert_eq!(key, b"key");
+                assert_eq!(size, TRANSACTION_SIZE_LIMIT + 1);
+            }
+            res => panic!("unexpected {:?}", res),
+        }
+
+        // Conflict ranges count towards the limit too
+        size.add(0, 100);
+        assert!(size.check(b"key", None, 0).is_ok());
+        assert!(size.check(b"key", None, 1).is_err());
+
+        size.reset();
+        assert!(size.check(b"key", None, TRANSACTION_SIZE_LIMIT).is_ok());
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let size = SizeTracker::default();
+        let code = |res: Result<(), SizeLimitError>| Error::from(res.unwrap_err()).code();
+        let long = vec![b'x'; VALUE_SIZE_LIMIT + 1];
+        assert_eq!(
+            code(size.check(b"key", None, TRANSACTION_SIZE_LIMIT + 1)),
+            2101
+        );
+        assert_eq!(code(size.check(&long[..KEY_SIZE_LIMIT + 1], None, 0)), 2102);
+        assert_eq!(code(size.check(b"key", Some(&long), 0)), 2103);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::error::Error;
+use crate::outputs::{Key, KeyValueArray, Value};
+use std::cell::Cell;
+use std::sync::Arc;
+use std::time::Duration;
+
+// What a transaction did, counted across all of its attempts
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TransactionStats {
+    pub reads: u64,
+    pub keys_read: u64,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+    // Ranges added through add_conflict_range. The ranges that reads and writes add implicitly
+    // aren't counted, as the client doesn't report them
+    pub explicit_conflict_ranges: u64,
+    // Attempts retried through on_error
+    pub retries: u32,
+    // How long the latest explicit get_read_version call took. The read version that the first
+    // read of an attempt fetches implicitly isn't timed, so this stays None without such a call.
+    pub read_version_latency: Option<Duration>,
+    // How long the latest commit took, whether it succeeded or not
+    pub commit_latency: Option<Duration>,
+}
+
+// How a transaction ended
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransactionOutcome {
+    Committed,
+    // Given up on after on_error failed
+    Failed(Error),
+    // Dropped without being committed
+    Dropped,
+}
+
+// Receives the stats of every transaction created by a Database once its outcome is known
+pub trait MetricsSink: Send + Sync {
+    fn record(&self, stats: &TransactionStats, outcome: TransactionOutcome);
+}
+
+#[derive(Default)]
+pub(crate) struct StatsTracker {
+    stats: Cell<TransactionStats>,
+    sink: Option<Arc<dyn MetricsSink>>,
+}
+
+impl StatsTracker {
+    pub(crate) fn new(sink: Option<Arc<dyn MetricsSink>>) -> Self {
+        StatsTracker {
+            stats: Cell::default(),
+            sink,
+        }
+    }
+
+    pub(crate) fn get(&self) -> TransactionStats {
+        self.stats.get()
+    }
+
+    fn update<F: FnOnce(&mut TransactionStats)>(&self, f: F) {
+        let mut stats = self.stats.get();
+        f(&mut stats);
+        self.stats.set(stats);
+    }
+
+    pub(crate) fn add_read(&self, keys: usize, bytes: usize) {
+        self.update(|stats| {
+            stats.reads += 1;
+            stats.keys_read += keys as u64;
+            stats.bytes_read += bytes as u64;
+        });
+    }
+
+    // The count_ functions count a read and pass its result through
+
+    pub(crate) fn count_value(&self, value: Option<Value>) -> Option<Value> {
+        match &value {
+            Some(val) => self.add_read(1, val.as_ref().len()),
+            None => self.add_read(0, 0),
+        }
+        value
+    }
+
+    pub(crate) fn count_key(&self, key: Key) -> Key {
+        self.add_read(1, key.as_ref().len());
+        key
+    }
+
+    pub(crate) fn count_range(&self, kvs: KeyValueArray) -> KeyValueArray {
+        let bytes = kvs.iter().map(|kv| kv.key().len() + kv.value().len()).sum();
+        self.add_read(kvs.len(), bytes);
+        kvs
+    }
+
+    pub(crate) fn add_write(&self, bytes: usize) {
+        self.update(|stats| stats.bytes_written += bytes as u64);
+    }
+
+    pub(crate) fn add_explicit_conflict_range(&self) {
+        self.update(|stats| stats.explicit_conflict_ranges += 1);
+    }
+
+    pub(crate) fn add_retry(&self) {
+        self.update(|stats| stats.retries += 1);
+    }
+
+    pub(crate) fn set_read_version_latency(&self, latency: Duration) {
+        self.update(|stats| stats.read_version_latency = Some(latency));
+    }
+
+    pub(crate) fn set_commit_latency(&self, latency: Duration) {
+        self.update(|stats| stats.commit_latency = Some(latency));
+    }
+
+    pub(crate) fn report(&self, outcome: TransactionOutcome) {
+        if let Some(sink) = &self.sink {
+            sink.record(&self.stats.get(), outcome);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::{database, Reports};
+    use crate::{ConflictRangeType, GetRangeOpt, KeySelector, TransactionOutcome};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_metrics_sink() {
+        let mut db = database();
+        let reports = Arc::new(Reports::default());
+        db.set_metrics_sink(reports.clone());
+        let other = database();
+        other.set(b"stats_test/a", b"12345").unwrap();
+        other.clear(b"stats_test/missing").unwrap();
+
+        let tran = db.create_transaction().unwrap();
+        tran.get(b"stats_test/a", false).unwrap();
+        tran.get(b"stats_test/missing", false).unwrap();
+        let opt = GetRangeOpt {
+            begin_selector: KeySelector::first_greater_or_equal(b"stats_test/a"),
+            end_selector: KeySelector::first_greater_or_equal(b"stats_test/b"),
+            ..GetRangeOpt::default()
+        };
+        tran.get_range(&opt).unwrap();
+        tran.set(b"stats_test/b", b"xy");
+        tran.add_conflict_range(b"stats_test/c", b"stats_test/d", ConflictRangeType::Read)
+            .unwrap();
+
+        // Another transaction writing a key read above makes the commit conflict
+        other.set(b"stats_test/a", b"other").unwrap();
+        let fail_tran = match tran.commit() {
+            Ok(_) => panic!("commit should have conflicted"),
+            Err(fail_tran) => fail_tran,
+        };
+        let tran = fail_tran
+            .on_error()
+            .map_err(|fail_tran| fail_tran.error())
+            .unwrap();
+        tran.commit()
+            .map_err(|fail_tran| fail_tran.error())
+            .unwrap();
+
+        // Only the transaction's outcome is reported, not the attempts it retried
+        assert_eq!(reports.outcomes(), vec![TransactionOutcome::Committed]);
+        let stats = reports.stats()[0];
+        assert_eq!(stats.reads, 3);
+        assert_eq!(stats.keys_read, 2);
+        // The value read by get, then both the key and the value read by get_range
+        assert_eq!(stats.bytes_read, 5 + 12 + 5);
+        assert_eq!(stats.bytes_written, 12 + 2);
+        assert_eq!(stats.explicit_conflict_ranges, 1);
+        assert_eq!(stats.retries, 1);
+        assert_eq!(stats.read_version_latency, None);
+        assert!(stats.commit_latency.is_some());
+    }
+}
@@ -0,0 +1,235 @@
+use crate::error::Error;
+#[cfg(feature = "tracing")]
+use crate::printable::Printable;
+use crate::transaction::KeySelector;
+#[cfg(feature = "tracing")]
+use std::fmt::{self, Display, Formatter};
+#[cfg(feature = "tracing")]
+use tracing::{field, Level, Span};
+
+// Keys longer than this are cut short in traces
+#[cfg(feature = "tracing")]
+const MAX_TRACED_KEY: usize = 100;
+
+// Formats a key as Printable does, but no more than MAX_TRACED_KEY bytes of it
+#[cfg(feature = "tracing")]
+struct TracedKey<'a>(&'a [u8]);
+
+#[cfg(feature = "tracing")]
+impl<'a> Display for TracedKey<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        if self.0.len() > MAX_TRACED_KEY {
+            write!(f, "{}...", Printable(&self.0[..MAX_TRACED_KEY]))
+        } else {
+            write!(f, "{}", Printable(self.0))
+        }
+    }
+}
+
+// The span of a transaction, which lives as long as the transaction and all of its attempts. Its
+// operations are recorded as events within it. Without the tracing feature, this does nothing.
+#[derive(Default)]
+pub(crate) struct TransactionTrace {
+    #[cfg(feature = "tracing")]
+    span: Option<Span>,
+}
+
+#[cfg(feature = "tracing")]
+impl TransactionTrace {
+    pub(crate) fn new() -> Self {
+        let span = tracing::debug_span!("transaction", attempt = 1u32, error = field::Empty);
+        TransactionTrace { span: Some(span) }
+    }
+
+    pub(crate) fn get(&self, key: &[u8], snapshot: bool) {
+        if let Some(span) = &self.span {
+            tracing::event!(parent: span, Level::TRACE, key = %TracedKey(key), snapshot, "get");
+        }
+    }
+
+    pub(crate) fn get_key(&self, selector: &KeySelector, snapshot: bool) {
+        if let Some(span) = &self.span {
+            tracing::event!(
+                parent: span,
+                Level::TRACE,
+                key = %TracedKey(selector.key),
+                equal = selector.equal,
+                offset = selector.offset,
+                snapshot,
+                "get_key"
+            );
+        }
+    }
+
+    pub(crate) fn get_range(&self, begin_key: &[u8], end_key: &[u8], limit: i32, snapshot: bool) {
+        if let Some(span) = &self.span {
+            tracing::event!(
+                parent: span,
+                Level::TRACE,
+                begin = %TracedKey(begin_key),
+                end = %TracedKey(end_key),
+                limit,
+                snapshot,
+                "get_range"
+            );
+        }
+    }
+
+    pub(crate) fn commit(&self, err: Option<Error>) {
+        if let Some(span) = &self.span {
+            match err {
+                Some(err) => {
+                    tracing::event!(parent: span, Level::DEBUG, error = err.code(), "commit")
+                }
+                None => tracing::event!(parent: span, Level::DEBUG, "commit"),
+            }
+        }
+    }
+
+    pub(crate) fn on_error(&self, err: Error) {
+        if let Some(span) = &self.span {
+            tracing::event!(parent: span, Level::DEBUG, error = err.code(), "on_error");
+        }
+    }
+
+    // Attempts are numbered from 1
+    pub(crate) fn attempt(&self, attempt: u32) {
+        if let Some(span) = &self.span {
+            span.record("attempt", attempt);
+        }
+    }
+
+    // Records the error the transaction was given up on
+    pub(crate) fn fail(&self, err: Error) {
+        if let Some(span) = &self.span {
+            span.record("error", err.code());
+        }
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+impl TransactionTrace {
+    pub(crate) fn new() -> Self {
+        TransactionTrace {}
+    }
+
+    pub(crate) fn get(&self, _key: &[u8], _snapshot: bool) {}
+
+    pub(crate) fn get_key(&self, _selector: &KeySelector, _snapshot: bool) {}
+
+    pub(crate) fn get_range(
+        &self,
+        _begin_key: &[u8],
+        _end_key: &[u8],
+        _limit: i32,
+        _snapshot: bool,
+    ) {
+    }
+
+    pub(crate) fn commit(&self, _err: Option<Error>) {}
+
+    pub(crate) fn on_error(&self, _err: Error) {}
+
+    pub(crate) fn attempt(&self, _attempt: u32) {}
+
+    pub(crate) fn fail(&self, _err: Error) {}
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod test {
+    use super::TransactionTrace;
+    use crate::error::Error;
+    use crate::transaction::KeySelector;
+    use std::collections::HashMap;
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    // Field values formatted with Debug, by field name
+    #[derive(Default)]
+    struct Fields(HashMap<String, String>);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    // Keeps the fields of every span, all of which it treats as one, and of every event
+    #[derive(Clone, Default)]
+    struct Capture {
+        span: Arc<Mutex<Fields>>,
+        events: Arc<Mutex<Vec<Fields>>>,
+    }
+
+    impl Capture {
+        fn span_field(&self, name: &str) -> Option<String> {
+            self.span.lock().unwrap().0.get(name).cloned()
+        }
+
+        fn event_field(&self, index: usize, name: &str) -> Option<String> {
+            self.events.lock().unwrap()[index].0.get(name).cloned()
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn new_span(&self, attrs: &Attributes) -> Id {
+            attrs.record(&mut *self.span.lock().unwrap());
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &Id, values: &Record) {
+            values.record(&mut *self.span.lock().unwrap());
+        }
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event) {
+            let mut fields = Fields::default();
+            event.record(&mut fields);
+            self.events.lock().unwrap().push(fields);
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[test]
+    fn test_transaction_trace() {
+        let capture = Capture::default();
+        tracing::subscriber::with_default(capture.clone(), || {
+            let trace = TransactionTrace::new();
+            assert_eq!(capture.span_field("attempt"), Some("1".to_string()));
+            assert_eq!(capture.span_field("error"), None);
+
+            trace.get(&[b'a'; 150], false);
+            trace.get_key(&KeySelector::first_greater_than(b"key"), false);
+            trace.attempt(2);
+            trace.fail(Error::from_code(1020));
+        });
+
+        assert_eq!(capture.span_field("attempt"), Some("2".to_string()));
+        assert_eq!(capture.span_field("error"), Some("1020".to_string()));
+
+        assert_eq!(capture.events.lock().unwrap().len(), 2);
+        let mut truncated = "a".repeat(100);
+        truncated.push_str("...");
+        assert_eq!(capture.event_field(0, "message"), Some("get".to_string()));
+        assert_eq!(capture.event_field(0, "key"), Some(truncated));
+        assert_eq!(
+            capture.event_field(1, "message"),
+            Some("get_key".to_string())
+        );
+        assert_eq!(capture.event_field(1, "key"), Some("key".to_string()));
+        assert_eq!(capture.event_field(1, "equal"), Some("true".to_string()));
+        assert_eq!(capture.event_field(1, "offset"), Some("1".to_string()));
+    }
+}
@@ -1,11 +1,22 @@
+use crate::address::{parse_addresses, AddressesError, StorageAddress};
 use crate::error::Error;
 use crate::future::Future;
+use crate::hooks::Hooks;
+use crate::mutation::{Mutation, Recorder};
 use crate::options::{ConflictRangeType, MutationType, StreamingMode, TransactionOption};
-use crate::outputs::{Key, KeyValueArray, StringArray, Value};
+use crate::outputs::{Key, KeyValueArray, Value};
+use crate::size::{SizeLimitError, SizeTracker, TransactionSize};
+use crate::stats::{MetricsSink, StatsTracker, TransactionOutcome, TransactionStats};
+use crate::trace::TransactionTrace;
+use crate::transaction_options::TransactionOptions;
 use foundationdb_sys as fdb;
+use std::cell::RefCell;
 use std::mem::replace;
 use std::os::raw::c_int;
 use std::ptr::null_mut;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Instant;
 
 pub struct KeySelector<'a> {
     pub key: &'a [u8],
@@ -59,15 +70,150 @@ impl<'a> Default for KeySelector<'a> {
     }
 }
 
+/*
+ * Reader
+ */
+
+// Implemented by everything that can perform reads. Whether the reads are snapshot reads is
+// decided by the implementing type rather than by a per-call flag, so GetRangeOpt::snapshot is
+// ignored.
+pub trait Reader {
+    fn get(&self, key: &[u8]) -> Result<Option<Value>, Error>;
+    fn get_key(&self, selector: KeySelector) -> Result<Key, Error>;
+    fn get_range(&self, opt: &GetRangeOpt) -> Result<KeyValueArray, Error>;
+    fn get_read_version(&self) -> Result<i64, Error>;
+}
+
 /*
  * Transaction
  */
 
+// Client side state that follows a transaction through commit and on_error
+#[derive(Default)]
+pub(crate) struct TransactionState {
+    pub(crate) options: TransactionOptions,
+    pub(crate) hooks: RefCell<Hooks>,
+    pub(crate) size: SizeTracker,
+    pub(crate) recorder: Recorder,
+    // Shared with the futures of async operations, which can't borrow the transaction
+    pub(crate) stats: Rc<StatsTracker>,
+    pub(crate) trace: TransactionTrace,
+}
+
+impl TransactionState {
+    // Forgets everything belonging to the attempt that failed. Stats cover every attempt, so they
+    // are kept.
+    pub(crate) fn reset(&self) {
+        self.hooks.replace(Hooks::default());
+        self.size.reset();
+        self.recorder.reset();
+    }
+}
+
 pub struct Transaction {
     pub(crate) tran: *mut fdb::FDBTransaction,
+    pub(crate) state: Box<TransactionState>,
 }
 
 impl Transaction {
+    pub(crate) fn new(
+        tran: *mut fdb::FDBTransaction,
+        options: TransactionOptions,
+        metrics: Option<Arc<dyn MetricsSink>>,
+    ) -> Result<Self, Error> {
+        let state = Box::new(TransactionState {
+            options,
+            stats: Rc::new(StatsTracker::new(metrics)),
+            trace: TransactionTrace::new(),
+            ..TransactionState::default()
+        });
+        let tran = Transaction { tran, state };
+        tran.state.options.apply(&tran)?;
+        Ok(tran)
+    }
+
+    // The versionstamp has to be asked for before committing, so only do it if someone wants it
+    pub(crate) fn versionstamp_for_hooks(&self) -> Option<Future> {
+        if self.state.hooks.borrow().has_on_commit() {
+            let fut = unsafe { fdb::fdb_transaction_get_versionstamp(self.tran) };
+            Some(Future::new(fut))
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn into_committed(mut self, versionstamp: Option<Future>) -> CommittedTransaction {
+        let committed = CommittedTransaction {
+            tran: replace(&mut self.tran, null_mut()),
+            mutations: self.state.recorder.take(),
+            stats: self.state.stats.get(),
+        };
+        self.state.stats.report(TransactionOutcome::Committed);
+
+        let hooks = self.state.hooks.replace(Hooks::default());
+        if hooks.has_on_commit() {
+            // Read-only transactions have neither a committed version nor a versionstamp
+            let version = committed.get_committed_version().unwrap_or(-1);
+            let versionstamp = versionstamp
+                .and_then(|fut| fut.block_until_ready().and_then(|fut| fut.into_key()).ok());
+            hooks.run_on_commit(version, versionstamp.as_ref().map(|key| key.as_ref()));
+        }
+
+        committed
+    }
+
+    // Ends a transaction that wrote nothing. There is nothing to send to the cluster, but unlike
+    // dropping it, this counts as committing it, so neither the abort hooks nor Dropped apply.
+    pub(crate) fn end_read_only(mut self) {
+        unsafe { fdb::fdb_transaction_destroy(replace(&mut self.tran, null_mut())) };
+        self.state.stats.report(TransactionOutcome::Committed);
+    }
+
+    // Ends this attempt with err, so that on_error can decide whether to retry it
+    pub fn into_failed(mut self, err: Error) -> FailedTransaction {
+        FailedTransaction {
+            tran: replace(&mut self.tran, null_mut()),
+            err: err.err,
+            state: replace(&mut self.state, Default::default()),
+        }
+    }
+
+    // What this transaction has done so far, across all of its attempts
+    pub fn stats(&self) -> TransactionStats {
+        self.state.stats.get()
+    }
+
+    // Starts keeping a copy of every mutation made to this transaction, in order. Recording
+    // continues across on_error, but only the mutations of the latest attempt are kept.
+    pub fn record_mutations(&self) {
+        self.state.recorder.start();
+    }
+
+    // The mutations recorded so far, if record_mutations was called
+    pub fn mutations(&self) -> Vec<Mutation> {
+        self.state.recorder.get()
+    }
+
+    // Registers a callback to run once this transaction has committed, receiving the committed
+    // version and the versionstamp. Callbacks registered on an attempt that ends up being retried
+    // by on_error are discarded without being run.
+    pub fn on_commit<F>(&self, f: F)
+    where
+        F: FnOnce(i64, Option<&[u8]>) + 'static,
+    {
+        self.state.hooks.borrow_mut().add_on_commit(Box::new(f));
+    }
+
+    // Registers a callback to run if this transaction is given up on, either because on_error
+    // failed or because it was dropped without being committed. The callback receives the error
+    // that ended it, if any.
+    pub fn on_abort<F>(&self, f: F)
+    where
+        F: FnOnce(Option<Error>) + 'static,
+    {
+        self.state.hooks.borrow_mut().add_on_abort(Box::new(f));
+    }
+
     pub fn set_option(&self, option: TransactionOption, value: &[u8]) -> Result<(), Error> {
         bail!(unsafe {
             fdb::fdb_transaction_set_option(
@@ -82,6 +228,7 @@ impl Transaction {
     }
 
     pub fn get(&self, key: &[u8], snapshot: bool) -> Result<Option<Value>, Error> {
+        self.state.trace.get(key, snapshot);
         let fut = unsafe {
             fdb::fdb_transaction_get(
                 self.tran,
@@ -93,9 +240,11 @@ impl Transaction {
         Future::new(fut)
             .block_until_ready()
             .and_then(|fut| fut.into_value())
+            .map(|value| self.state.stats.count_value(value))
     }
 
     pub fn get_key(&self, selector: KeySelector, snapshot: bool) -> Result<Key, Error> {
+        self.state.trace.get_key(&selector, snapshot);
         let fut = unsafe {
             fdb::fdb_transaction_get_key(
                 self.tran,
@@ -109,9 +258,16 @@ impl Transaction {
         Future::new(fut)
             .block_until_ready()
             .and_then(|fut| fut.into_key())
+            .map(|key| self.state.stats.count_key(key))
     }
 
-    pub(crate) fn get_range_raw(&self, opt: &GetRangeOpt) -> *mut fdb::FDBFuture {
+    pub(crate) fn get_range_raw(&self, opt: &GetRangeOpt, snapshot: bool) -> *mut fdb::FDBFuture {
+        self.state.trace.get_range(
+            opt.begin_selector.key,
+            opt.end_selector.key,
+            opt.limit,
+            snapshot,
+        );
         unsafe {
             fdb::fdb_transaction_get_range(
                 self.tran,
@@ -127,20 +283,37 @@ impl Transaction {
                 opt.target_bytes as c_int,
                 opt.mode.as_c_enum(),
                 opt.iteration as c_int,
-                opt.snapshot as fdb::fdb_bool_t,
+                snapshot as fdb::fdb_bool_t,
                 opt.reverse as fdb::fdb_bool_t,
             )
         }
     }
 
     pub fn get_range(&self, opt: &GetRangeOpt) -> Result<KeyValueArray, Error> {
-        let fut = self.get_range_raw(opt);
+        self.get_range_with(opt, opt.snapshot)
+    }
+
+    // For Reader impls, which decide themselves whether reads are snapshot reads
+    pub(crate) fn get_range_with(
+        &self,
+        opt: &GetRangeOpt,
+        snapshot: bool,
+    ) -> Result<KeyValueArray, Error> {
+        let fut = self.get_range_raw(opt, snapshot);
         Future::new(fut)
             .block_until_ready()
             .and_then(|fut| fut.into_keyvalue_array())
+            .map(|kvs| self.state.stats.count_range(kvs))
     }
 
     pub fn atomic_op(&self, key: &[u8], param: &[u8], mut_type: MutationType) {
+        self.state.size.add_key_write(key, param);
+        self.state.stats.add_write(key.len() + param.len());
+        self.state.recorder.record(|| Mutation::AtomicOp {
+            key: key.to_vec(),
+            param: param.to_vec(),
+            mut_type,
+        });
         unsafe {
             fdb::fdb_transaction_atomic_op(
                 self.tran,
@@ -154,6 +327,12 @@ impl Transaction {
     }
 
     pub fn set(&self, key: &[u8], value: &[u8]) {
+        self.state.size.add_key_write(key, value);
+        self.state.stats.add_write(key.len() + value.len());
+        self.state.recorder.record(|| Mutation::Set {
+            key: key.to_vec(),
+            value: value.to_vec(),
+        });
         unsafe {
             fdb::fdb_transaction_set(
                 self.tran,
@@ -166,10 +345,19 @@ impl Transaction {
     }
 
     pub fn clear(&self, key: &[u8]) {
+        self.state.size.add_key_write(key, &[]);
+        self.state.stats.add_write(key.len());
+        self.state.recorder.record(|| Mutation::Clear { key: key.to_vec() });
         unsafe { fdb::fdb_transaction_clear(self.tran, key.as_ptr(), key.len() as c_int) };
     }
 
     pub fn clear_range(&self, begin_key: &[u8], end_key: &[u8]) {
+        self.state.size.add_range_write(begin_key, end_key);
+        self.state.stats.add_write(begin_key.len() + end_key.len());
+        self.state.recorder.record(|| Mutation::ClearRange {
+            begin_key: begin_key.to_vec(),
+            end_key: end_key.to_vec(),
+        });
         unsafe {
             fdb::fdb_transaction_clear_range(
                 self.tran,
@@ -181,16 +369,64 @@ impl Transaction {
         };
     }
 
-    pub fn commit(mut self) -> Result<CommittedTransaction, FailedTransaction> {
+    // The try_ variants of the write operations check FoundationDB's key, value and transaction
+    // size limits up front, instead of letting the commit fail
+
+    pub fn try_atomic_op(
+        &self,
+        key: &[u8],
+        param: &[u8],
+        mut_type: MutationType,
+    ) -> Result<(), SizeLimitError> {
+        self.state.size.check(key, Some(param), key.len() * 3 + param.len() + 1)?;
+        self.atomic_op(key, param, mut_type);
+        Ok(())
+    }
+
+    pub fn try_set(&self, key: &[u8], value: &[u8]) -> Result<(), SizeLimitError> {
+        self.state.size.check(key, Some(value), key.len() * 3 + value.len() + 1)?;
+        self.set(key, value);
+        Ok(())
+    }
+
+    pub fn try_clear(&self, key: &[u8]) -> Result<(), SizeLimitError> {
+        self.state.size.check(key, None, key.len() * 3 + 1)?;
+        self.clear(key);
+        Ok(())
+    }
+
+    pub fn try_clear_range(&self, begin_key: &[u8], end_key: &[u8]) -> Result<(), SizeLimitError> {
+        let added = (begin_key.len() + end_key.len()) * 2;
+        self.state.size.check(begin_key, None, added)?;
+        self.state.size.check(end_key, None, added)?;
+        self.clear_range(begin_key, end_key);
+        Ok(())
+    }
+
+    // An estimate of the size of this transaction, tracked as it's written to
+    pub fn approximate_size(&self) -> TransactionSize {
+        self.state.size.get()
+    }
+
+    // The size of this transaction as estimated by the client library
+    #[cfg(feature = "fdb-6_2")]
+    pub fn get_approximate_size(&self) -> Result<i64, Error> {
+        let fut = unsafe { fdb::fdb_transaction_get_approximate_size(self.tran) };
+        Future::new(fut)
+            .block_until_ready()
+            .and_then(|fut| fut.into_int64())
+    }
+
+    pub fn commit(self) -> Result<CommittedTransaction, FailedTransaction> {
+        let versionstamp = self.versionstamp_for_hooks();
+        let start = Instant::now();
         let fut = unsafe { fdb::fdb_transaction_commit(self.tran) };
-        match Future::new(fut).block_until_ready() {
-            Ok(_) => Ok(CommittedTransaction {
-                tran: replace(&mut self.tran, null_mut()),
-            }),
-            Err(err) => Err(FailedTransaction {
-                tran: replace(&mut self.tran, null_mut()),
-                err: err.err,
-            }),
+        let res = Future::new(fut).block_until_ready();
+        self.state.stats.set_commit_latency(start.elapsed());
+        self.state.trace.commit(res.as_ref().err().copied());
+        match res {
+            Ok(_) => Ok(self.into_committed(versionstamp)),
+            Err(err) => Err(self.into_failed(err)),
         }
     }
 
@@ -205,19 +441,23 @@ impl Transaction {
     }
 
     pub fn get_read_version(&self) -> Result<i64, Error> {
+        let start = Instant::now();
         let fut = unsafe { fdb::fdb_transaction_get_read_version(self.tran) };
-        Future::new(fut)
+        let version = Future::new(fut)
             .block_until_ready()
-            .and_then(|fut| fut.into_version())
+            .and_then(|fut| fut.into_version())?;
+        self.state.stats.set_read_version_latency(start.elapsed());
+        Ok(version)
     }
 
-    pub fn get_addresses_for_key(&self, key: &[u8]) -> Result<StringArray, Error> {
+    pub fn get_addresses_for_key(&self, key: &[u8]) -> Result<Vec<StorageAddress>, AddressesError> {
         let fut = unsafe {
             fdb::fdb_transaction_get_addresses_for_key(self.tran, key.as_ptr(), key.len() as c_int)
         };
-        Future::new(fut)
+        let arr = Future::new(fut)
             .block_until_ready()
-            .and_then(|fut| fut.into_string_array())
+            .and_then(|fut| fut.into_string_array())?;
+        parse_addresses(arr)
     }
 
     pub fn get_versionstamp(&self) -> Result<Key, Error> {
@@ -233,6 +473,8 @@ impl Transaction {
         end_key: &'a [u8],
         range_type: ConflictRangeType,
     ) -> Result<(), Error> {
+        self.state.size.add(0, begin_key.len() + end_key.len());
+        self.state.stats.add_explicit_conflict_range();
         bail!(unsafe {
             fdb::fdb_transaction_add_conflict_range(
                 self.tran,
@@ -248,6 +490,13 @@ impl Transaction {
 
     pub fn reset(&self) {
         unsafe { fdb::fdb_transaction_reset(self.tran) };
+        self.state.reset();
+        // These options were already accepted when the transaction was created
+        let _ = self.state.options.apply(self);
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot { tran: self }
     }
 
     pub fn cancel(&self) {
@@ -259,8 +508,137 @@ impl Drop for Transaction {
     fn drop(&mut self) {
         if !self.tran.is_null() {
             unsafe { fdb::fdb_transaction_destroy(self.tran) };
+            self.state.hooks.replace(Hooks::default()).run_on_abort(None);
+            self.state.stats.report(TransactionOutcome::Dropped);
+        }
+    }
+}
+
+/*
+ * ReadTransaction
+ */
+
+pub struct ReadTransaction {
+    pub(crate) tran: Transaction,
+}
+
+impl ReadTransaction {
+    pub fn set_option(&self, option: TransactionOption, value: &[u8]) -> Result<(), Error> {
+        self.tran.set_option(option, value)
+    }
+
+    pub fn get(&self, key: &[u8], snapshot: bool) -> Result<Option<Value>, Error> {
+        self.tran.get(key, snapshot)
+    }
+
+    pub fn get_key(&self, selector: KeySelector, snapshot: bool) -> Result<Key, Error> {
+        self.tran.get_key(selector, snapshot)
+    }
+
+    pub fn get_range(&self, opt: &GetRangeOpt) -> Result<KeyValueArray, Error> {
+        self.tran.get_range(opt)
+    }
+
+    pub fn set_read_version(&self, version: i64) {
+        self.tran.set_read_version(version)
+    }
+
+    pub fn get_read_version(&self) -> Result<i64, Error> {
+        self.tran.get_read_version()
+    }
+
+    // Nothing was written, so there is nothing to commit, but the transaction still ends as
+    // committed rather than dropped
+    pub fn commit(self) {
+        self.tran.end_read_only()
+    }
+
+    pub fn on_error(self, err: Error) -> Result<ReadTransaction, Error> {
+        match self.tran.into_failed(err).on_error() {
+            Ok(tran) => Ok(ReadTransaction { tran }),
+            Err(fail_tran) => Err(fail_tran.into_error()),
         }
     }
+
+    pub fn snapshot(&self) -> Snapshot {
+        self.tran.snapshot()
+    }
+
+    pub fn stats(&self) -> TransactionStats {
+        self.tran.stats()
+    }
+
+    pub fn reset(&self) {
+        self.tran.reset()
+    }
+
+    pub fn cancel(&self) {
+        self.tran.cancel()
+    }
+}
+
+impl Reader for Transaction {
+    fn get(&self, key: &[u8]) -> Result<Option<Value>, Error> {
+        Transaction::get(self, key, false)
+    }
+
+    fn get_key(&self, selector: KeySelector) -> Result<Key, Error> {
+        Transaction::get_key(self, selector, false)
+    }
+
+    fn get_range(&self, opt: &GetRangeOpt) -> Result<KeyValueArray, Error> {
+        self.get_range_with(opt, false)
+    }
+
+    fn get_read_version(&self) -> Result<i64, Error> {
+        Transaction::get_read_version(self)
+    }
+}
+
+impl Reader for ReadTransaction {
+    fn get(&self, key: &[u8]) -> Result<Option<Value>, Error> {
+        ReadTransaction::get(self, key, false)
+    }
+
+    fn get_key(&self, selector: KeySelector) -> Result<Key, Error> {
+        ReadTransaction::get_key(self, selector, false)
+    }
+
+    fn get_range(&self, opt: &GetRangeOpt) -> Result<KeyValueArray, Error> {
+        self.tran.get_range_with(opt, false)
+    }
+
+    fn get_read_version(&self) -> Result<i64, Error> {
+        ReadTransaction::get_read_version(self)
+    }
+}
+
+/*
+ * Snapshot
+ */
+
+// A view of a transaction where every read is a snapshot read, adding no read conflict ranges
+#[derive(Clone, Copy)]
+pub struct Snapshot<'a> {
+    pub(crate) tran: &'a Transaction,
+}
+
+impl<'a> Reader for Snapshot<'a> {
+    fn get(&self, key: &[u8]) -> Result<Option<Value>, Error> {
+        self.tran.get(key, true)
+    }
+
+    fn get_key(&self, selector: KeySelector) -> Result<Key, Error> {
+        self.tran.get_key(selector, true)
+    }
+
+    fn get_range(&self, opt: &GetRangeOpt) -> Result<KeyValueArray, Error> {
+        self.tran.get_range_with(opt, true)
+    }
+
+    fn get_read_version(&self) -> Result<i64, Error> {
+        self.tran.get_read_version()
+    }
 }
 
 /*
@@ -269,9 +647,20 @@ impl Drop for Transaction {
 
 pub struct CommittedTransaction {
     pub(crate) tran: *mut fdb::FDBTransaction,
+    pub(crate) mutations: Vec<Mutation>,
+    pub(crate) stats: TransactionStats,
 }
 
 impl CommittedTransaction {
+    // The mutations committed, if record_mutations was called on the transaction
+    pub fn mutations(&self) -> &[Mutation] {
+        &self.mutations
+    }
+
+    pub fn stats(&self) -> &TransactionStats {
+        &self.stats
+    }
+
     pub fn get_committed_version(&self) -> Result<i64, Error> {
         let mut version = 0;
         bail!(unsafe { fdb::fdb_transaction_get_committed_version(self.tran, &mut version) });
@@ -292,15 +681,29 @@ impl Drop for CommittedTransaction {
 pub struct FailedTransaction {
     pub(crate) tran: *mut fdb::FDBTransaction,
     pub(crate) err: fdb::fdb_error_t,
+    pub(crate) state: Box<TransactionState>,
 }
 
 impl FailedTransaction {
+    pub(crate) fn into_reset(mut self) -> Result<Transaction, FailedTransaction> {
+        let tran = Transaction {
+            tran: replace(&mut self.tran, null_mut()),
+            state: replace(&mut self.state, Default::default()),
+        };
+        tran.state.reset();
+        tran.state.stats.add_retry();
+        tran.state.trace.attempt(tran.state.stats.get().retries + 1);
+        match tran.state.options.apply(&tran) {
+            Ok(()) => Ok(tran),
+            Err(err) => Err(tran.into_failed(err)),
+        }
+    }
+
     pub fn on_error(mut self) -> Result<Transaction, FailedTransaction> {
+        self.state.trace.on_error(self.error());
         let fut = unsafe { fdb::fdb_transaction_on_error(self.tran, self.err) };
         match Future::new(fut).block_until_ready() {
-            Ok(_) => Ok(Transaction {
-                tran: replace(&mut self.tran, null_mut()),
-            }),
+            Ok(_) => self.into_reset(),
             Err(err) => {
                 self.err = err.err;
                 Err(self)
@@ -308,6 +711,14 @@ impl FailedTransaction {
         }
     }
 
+    pub fn stats(&self) -> TransactionStats {
+        self.state.stats.get()
+    }
+
+    pub fn error(&self) -> Error {
+        Error { err: self.err }
+    }
+
     pub fn into_error(self) -> Error {
         Error { err: self.err }
     }
@@ -318,5 +729,75 @@ impl Drop for FailedTransaction {
         if !self.tran.is_null() {
             unsafe { fdb::fdb_transaction_destroy(self.tran) };
         }
+        let hooks = self.state.hooks.replace(Hooks::default());
+        hooks.run_on_abort(Some(Error { err: self.err }));
+        // After a successful on_error the state has moved on to the new attempt, and this is empty
+        self.state.trace.fail(Error { err: self.err });
+        self.state.stats.report(TransactionOutcome::Failed(Error { err: self.err }));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::{database, Reports};
+    use crate::{Database, GetRangeOpt, KeySelector, Reader, Transaction, TransactionOutcome};
+    use std::sync::Arc;
+
+    // Reads the range holding key through read, with GetRangeOpt::snapshot set to snapshot, has
+    // another transaction write key, then commits a write of its own. The commit only conflicts
+    // if the read added a read conflict range.
+    fn conflicts<F>(db: &Database, key: &[u8], snapshot: bool, read: F) -> bool
+    where
+        F: FnOnce(&Transaction, &GetRangeOpt),
+    {
+        let mut end = key.to_vec();
+        end.push(0x00);
+        let opt = GetRangeOpt {
+            begin_selector: KeySelector::first_greater_or_equal(key),
+            end_selector: KeySelector::first_greater_or_equal(&end),
+            snapshot,
+            ..GetRangeOpt::default()
+        };
+
+        let tran = db.create_transaction().unwrap();
+        read(&tran, &opt);
+
+        let other = db.create_transaction().unwrap();
+        other.set(key, b"other");
+        other.commit().map_err(|fail_tran| fail_tran.error()).unwrap();
+
+        tran.set(b"reader_test_unrelated", b"");
+        tran.commit().is_err()
+    }
+
+    #[test]
+    fn test_reader_snapshot() {
+        let db = database();
+
+        // Reader impls ignore GetRangeOpt::snapshot
+        assert!(conflicts(&db, b"reader_test", true, |tran, opt| {
+            Reader::get_range(tran, opt).unwrap();
+        }));
+        assert!(!conflicts(&db, b"reader_test", false, |tran, opt| {
+            Reader::get_range(&tran.snapshot(), opt).unwrap();
+        }));
+    }
+
+    #[test]
+    fn test_read_transaction_commit() {
+        let mut db = database();
+        let reports = Arc::new(Reports::default());
+        db.set_metrics_sink(reports.clone());
+
+        let tran = db.create_read_transaction().unwrap();
+        tran.get(b"read_transaction_test", false).unwrap();
+        tran.commit();
+        assert_eq!(reports.outcomes(), vec![TransactionOutcome::Committed]);
+
+        drop(db.create_read_transaction().unwrap());
+        assert_eq!(
+            reports.outcomes(),
+            vec![TransactionOutcome::Committed, TransactionOutcome::Dropped]
+        );
     }
 }
@@ -1,12 +1,15 @@
+use crate::address::{parse_addresses, AddressesError, StorageAddress};
 use crate::error::Error;
 use crate::future_async::FutureAsync;
-use crate::outputs::{Key, KeyValueArray, StringArray, Value};
-use crate::transaction::{CommittedTransaction, FailedTransaction, GetRangeOpt, KeySelector, Transaction};
+use crate::outputs::{Key, KeyValueArray, Value};
+use crate::transaction::{
+    CommittedTransaction, FailedTransaction, GetRangeOpt, KeySelector, ReadTransaction, Snapshot,
+    Transaction,
+};
 use foundationdb_sys as fdb;
 use futures::{future::ready, Future, FutureExt, TryFutureExt};
-use std::mem::replace;
 use std::os::raw::c_int;
-use std::ptr::null_mut;
+use std::time::Instant;
 
 /*
  * Transaction
@@ -18,6 +21,8 @@ impl Transaction {
         key: &'a [u8],
         snapshot: bool,
     ) -> impl Future<Output = Result<Option<Value>, Error>> {
+        self.state.trace.get(key, snapshot);
+        let stats = self.state.stats.clone();
         let fut = unsafe {
             fdb::fdb_transaction_get(
                 self.tran,
@@ -26,7 +31,9 @@ impl Transaction {
                 snapshot as fdb::fdb_bool_t,
             )
         };
-        FutureAsync::new(fut).and_then(|fut| ready(fut.into_value()))
+        FutureAsync::new(fut)
+            .and_then(|fut| ready(fut.into_value()))
+            .map_ok(move |value| stats.count_value(value))
     }
 
     pub fn get_key_async<'a>(
@@ -34,6 +41,8 @@ impl Transaction {
         selector: KeySelector<'a>,
         snapshot: bool,
     ) -> impl Future<Output = Result<Key, Error>> {
+        self.state.trace.get_key(&selector, snapshot);
+        let stats = self.state.stats.clone();
         let fut = unsafe {
             fdb::fdb_transaction_get_key(
                 self.tran,
@@ -44,35 +53,49 @@ impl Transaction {
                 snapshot as fdb::fdb_bool_t,
             )
         };
-        FutureAsync::new(fut).and_then(|fut| ready(fut.into_key()))
+        FutureAsync::new(fut)
+            .and_then(|fut| ready(fut.into_key()))
+            .map_ok(move |key| stats.count_key(key))
     }
 
     pub fn get_range_async<'a>(
         &'a self,
         opt: &'a GetRangeOpt<'a>,
     ) -> impl Future<Output = Result<KeyValueArray, Error>> {
-        let fut = self.get_range_raw(opt);
-        FutureAsync::new(fut).and_then(|fut| ready(fut.into_keyvalue_array()))
+        let stats = self.state.stats.clone();
+        let fut = self.get_range_raw(opt, opt.snapshot);
+        FutureAsync::new(fut)
+            .and_then(|fut| ready(fut.into_keyvalue_array()))
+            .map_ok(move |kvs| stats.count_range(kvs))
     }
 
     pub fn commit_async(
-        mut self,
+        self,
     ) -> impl Future<Output = Result<CommittedTransaction, FailedTransaction>> {
+        let versionstamp = self.versionstamp_for_hooks();
+        let start = Instant::now();
         let fut = unsafe { fdb::fdb_transaction_commit(self.tran) };
-        FutureAsync::new(fut).map(move |res| match res {
-            Ok(_) => Ok(CommittedTransaction {
-                tran: replace(&mut self.tran, null_mut()),
-            }),
-            Err(err) => Err(FailedTransaction {
-                tran: replace(&mut self.tran, null_mut()),
-                err: err.err,
-            }),
+        FutureAsync::new(fut).map(move |res| {
+            self.state.stats.set_commit_latency(start.elapsed());
+            self.state.trace.commit(res.as_ref().err().copied());
+            match res {
+                Ok(_) => Ok(self.into_committed(versionstamp)),
+                Err(err) => Err(self.into_failed(err)),
+            }
         })
     }
 
     pub fn get_read_version_async(&self) -> impl Future<Output = Result<i64, Error>> {
+        let stats = self.state.stats.clone();
+        let start = Instant::now();
         let fut = unsafe { fdb::fdb_transaction_get_read_version(self.tran) };
-        FutureAsync::new(fut).and_then(|fut| ready(fut.into_version()))
+        FutureAsync::new(fut)
+            .and_then(|fut| ready(fut.into_version()))
+            .inspect(move |res| {
+                if res.is_ok() {
+                    stats.set_read_version_latency(start.elapsed());
+                }
+            })
     }
 
     pub fn watch_async<'a>(
@@ -87,11 +110,20 @@ impl Transaction {
     pub fn get_addresses_for_key_async<'a>(
         &'a self,
         key: &'a [u8],
-    ) -> impl Future<Output = Result<StringArray, Error>> {
+    ) -> impl Future<Output = Result<Vec<StorageAddress>, AddressesError>> {
         let fut = unsafe {
             fdb::fdb_transaction_get_addresses_for_key(self.tran, key.as_ptr(), key.len() as c_int)
         };
-        FutureAsync::new(fut).and_then(|fut| ready(fut.into_string_array()))
+        FutureAsync::new(fut)
+            .and_then(|fut| ready(fut.into_string_array()))
+            .map_err(AddressesError::from)
+            .and_then(|arr| ready(parse_addresses(arr)))
+    }
+
+    #[cfg(feature = "fdb-6_2")]
+    pub fn get_approximate_size_async(&self) -> impl Future<Output = Result<i64, Error>> {
+        let fut = unsafe { fdb::fdb_transaction_get_approximate_size(self.tran) };
+        FutureAsync::new(fut).and_then(|fut| ready(fut.into_int64()))
     }
 
     pub fn get_versionstamp_async(&self) -> impl Future<Output = Result<Key, Error>> {
@@ -100,6 +132,81 @@ impl Transaction {
     }
 }
 
+/*
+ * ReadTransaction
+ */
+
+impl ReadTransaction {
+    pub fn get_async<'a>(
+        &'a self,
+        key: &'a [u8],
+        snapshot: bool,
+    ) -> impl Future<Output = Result<Option<Value>, Error>> {
+        self.tran.get_async(key, snapshot)
+    }
+
+    pub fn get_key_async<'a>(
+        &'a self,
+        selector: KeySelector<'a>,
+        snapshot: bool,
+    ) -> impl Future<Output = Result<Key, Error>> {
+        self.tran.get_key_async(selector, snapshot)
+    }
+
+    pub fn get_range_async<'a>(
+        &'a self,
+        opt: &'a GetRangeOpt<'a>,
+    ) -> impl Future<Output = Result<KeyValueArray, Error>> {
+        self.tran.get_range_async(opt)
+    }
+
+    pub fn get_read_version_async(&self) -> impl Future<Output = Result<i64, Error>> {
+        self.tran.get_read_version_async()
+    }
+
+    pub fn on_error_async(
+        self,
+        err: Error,
+    ) -> impl Future<Output = Result<ReadTransaction, Error>> {
+        self.tran.into_failed(err).on_error_async().map(|res| match res {
+            Ok(tran) => Ok(ReadTransaction { tran }),
+            Err(fail_tran) => Err(fail_tran.into_error()),
+        })
+    }
+}
+
+/*
+ * Snapshot
+ */
+
+impl<'a> Snapshot<'a> {
+    pub fn get_async(&self, key: &'a [u8]) -> impl Future<Output = Result<Option<Value>, Error>> {
+        self.tran.get_async(key, true)
+    }
+
+    pub fn get_key_async(
+        &self,
+        selector: KeySelector<'a>,
+    ) -> impl Future<Output = Result<Key, Error>> {
+        self.tran.get_key_async(selector, true)
+    }
+
+    pub fn get_range_async(
+        &self,
+        opt: &'a GetRangeOpt<'a>,
+    ) -> impl Future<Output = Result<KeyValueArray, Error>> {
+        let stats = self.tran.state.stats.clone();
+        let fut = self.tran.get_range_raw(opt, true);
+        FutureAsync::new(fut)
+            .and_then(|fut| ready(fut.into_keyvalue_array()))
+            .map_ok(move |kvs| stats.count_range(kvs))
+    }
+
+    pub fn get_read_version_async(&self) -> impl Future<Output = Result<i64, Error>> {
+        self.tran.get_read_version_async()
+    }
+}
+
 /*
  * FailedTransaction
  */
@@ -108,11 +215,10 @@ impl FailedTransaction {
     pub fn on_error_async(
         mut self,
     ) -> impl Future<Output = Result<Transaction, FailedTransaction>> {
+        self.state.trace.on_error(self.error());
         let fut = unsafe { fdb::fdb_transaction_on_error(self.tran, self.err) };
         FutureAsync::new(fut).map(|res| match res {
-            Ok(_) => Ok(Transaction {
-                tran: replace(&mut self.tran, null_mut()),
-            }),
+            Ok(_) => self.into_reset(),
             Err(err) => {
                 self.err = err.err;
                 Err(self)
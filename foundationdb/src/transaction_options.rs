@@ -0,0 +1,82 @@
+use crate::error::Error;
+use crate::options::TransactionOption;
+use crate::transaction::Transaction;
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TransactionPriority {
+    Default,
+    Batch,
+    SystemImmediate,
+}
+
+impl Default for TransactionPriority {
+    fn default() -> Self {
+        TransactionPriority::Default
+    }
+}
+
+// A set of transaction options applied whenever a transaction is created, and again each time
+// it is reset by on_error, since FoundationDB forgets most options on reset
+#[derive(Clone, Default)]
+pub struct TransactionOptions {
+    pub timeout: Option<Duration>,
+    pub retry_limit: Option<i64>,
+    pub max_retry_delay: Option<Duration>,
+    pub priority: TransactionPriority,
+    pub read_your_writes_disable: bool,
+    pub causal_read_risky: bool,
+    pub causal_write_risky: bool,
+    pub lock_aware: bool,
+    pub read_lock_aware: bool,
+    pub access_system_keys: bool,
+    pub read_system_keys: bool,
+}
+
+fn duration_millis(duration: Duration) -> i64 {
+    duration.as_secs() as i64 * 1000 + i64::from(duration.subsec_millis())
+}
+
+impl TransactionOptions {
+    pub(crate) fn apply(&self, tran: &Transaction) -> Result<(), Error> {
+        if let Some(timeout) = self.timeout {
+            let value = duration_millis(timeout).to_le_bytes();
+            tran.set_option(TransactionOption::Timeout, &value)?;
+        }
+
+        if let Some(retry_limit) = self.retry_limit {
+            tran.set_option(TransactionOption::RetryLimit, &retry_limit.to_le_bytes())?;
+        }
+
+        if let Some(max_retry_delay) = self.max_retry_delay {
+            let value = duration_millis(max_retry_delay).to_le_bytes();
+            tran.set_option(TransactionOption::MaxRetryDelay, &value)?;
+        }
+
+        match self.priority {
+            TransactionPriority::Default => {}
+            TransactionPriority::Batch => tran.set_option(TransactionOption::PriorityBatch, &[])?,
+            TransactionPriority::SystemImmediate => {
+                tran.set_option(TransactionOption::PrioritySystemImmediate, &[])?
+            }
+        }
+
+        let flags = [
+            (self.read_your_writes_disable, TransactionOption::ReadYourWritesDisable),
+            (self.causal_read_risky, TransactionOption::CausalReadRisky),
+            (self.causal_write_risky, TransactionOption::CausalWriteRisky),
+            (self.lock_aware, TransactionOption::LockAware),
+            (self.read_lock_aware, TransactionOption::ReadLockAware),
+            (self.access_system_keys, TransactionOption::AccessSystemKeys),
+            (self.read_system_keys, TransactionOption::ReadSystemKeys),
+        ];
+
+        for &(enabled, option) in flags.iter() {
+            if enabled {
+                tran.set_option(option, &[])?;
+            }
+        }
+
+        Ok(())
+    }
+}